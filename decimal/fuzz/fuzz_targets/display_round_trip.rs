@@ -0,0 +1,12 @@
+//! Formats arbitrary decimals and parses them back, which must give back exactly the
+//! same unscaled value and scale.
+#![no_main]
+
+use decimal::Decimal;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (i64, i16)| {
+    let d = Decimal::new(input.0, input.1 as i32);
+    let s = d.to_string();
+    assert_eq!(Ok(d), s.parse(), "{:?} displayed as {:?}", d, s);
+});
@@ -0,0 +1,114 @@
+//! Runs sequences of arithmetic on a `Decimal` alongside a big-integer model of what
+//! each operation should produce.
+//!
+//! The operators panic on overflow, so the model decides first whether each step
+//! overflows (skipping it if so) and otherwise checks the result exactly. Division
+//! and remainder go through `try_div`/`try_rem`, whose errors are checked too.
+#![no_main]
+
+use decimal::{ArithmeticError, Decimal};
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use num_bigint::BigInt;
+use num_traits::Pow;
+
+#[derive(Arbitrary, Debug)]
+struct Operand {
+    unscaled: i64,
+    scale: i8,
+}
+
+impl Operand {
+    fn decimal(&self) -> Decimal {
+        Decimal::new(self.unscaled, self.scale as i32)
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Add(Operand),
+    Sub(Operand),
+    Mul(Operand),
+    Div(Operand),
+    Rem(Operand),
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    start: Operand,
+    ops: Vec<Op>,
+}
+
+/// The unscaled value of `d` brought up to `scale`, if the result still fits in an `i64`.
+fn aligned(d: Decimal, scale: i32) -> Option<BigInt> {
+    let places = (scale - d.scale) as u32;
+    fits(BigInt::from(d.unscaled) * BigInt::from(10).pow(places))
+}
+
+fn fits(n: BigInt) -> Option<BigInt> {
+    i64::try_from(&n).ok().map(|_| n)
+}
+
+fn decimal(n: BigInt, scale: i32) -> Decimal {
+    Decimal::new(i64::try_from(&n).unwrap(), scale)
+}
+
+/// The exact sum or difference, or `None` if `+`/`-` would overflow.
+fn model_add(a: Decimal, b: Decimal, negate: bool) -> Option<Decimal> {
+    let scale = a.scale.max(b.scale);
+    let (x, y) = (aligned(a, scale)?, aligned(b, scale)?);
+    let result = if negate { x - y } else { x + y };
+    fits(result).map(|n| decimal(n, scale))
+}
+
+fn model_mul(a: Decimal, b: Decimal) -> Option<Decimal> {
+    let scale = a.scale.checked_add(b.scale)?;
+    fits(BigInt::from(a.unscaled) * BigInt::from(b.unscaled)).map(|n| decimal(n, scale))
+}
+
+/// What `try_div` (or `try_rem`, if `remainder`) should return.
+fn model_div(a: Decimal, b: Decimal, remainder: bool) -> Result<Decimal, ArithmeticError> {
+    if b.unscaled == 0 {
+        return Err(ArithmeticError::DivisionByZero);
+    }
+    let scale = a.scale.max(b.scale);
+    let x = aligned(a, scale).ok_or(ArithmeticError::Overflow)?;
+    let y = BigInt::from(b.unscaled);
+    if remainder {
+        // BigInt's % truncates, like i64's.
+        return Ok(decimal(&x % &y, scale));
+    }
+    let quotient = fits(&x / &y).ok_or(ArithmeticError::Overflow)?;
+    let scale = scale.checked_sub(b.scale).ok_or(ArithmeticError::ScaleOverflow)?;
+    Ok(decimal(quotient, scale))
+}
+
+fuzz_target!(|input: Input| {
+    let mut current = input.start.decimal();
+    for op in input.ops.iter().take(32) {
+        let next = match op {
+            Op::Add(b) => model_add(current, b.decimal(), false).inspect(|&expected| {
+                assert_eq!(expected, current + b.decimal(), "{:?} + {:?}", current, b);
+            }),
+            Op::Sub(b) => model_add(current, b.decimal(), true).inspect(|&expected| {
+                assert_eq!(expected, current - b.decimal(), "{:?} - {:?}", current, b);
+            }),
+            Op::Mul(b) => model_mul(current, b.decimal()).inspect(|&expected| {
+                assert_eq!(expected, current * b.decimal(), "{:?} * {:?}", current, b);
+            }),
+            Op::Div(b) => {
+                let expected = model_div(current, b.decimal(), false);
+                assert_eq!(expected, current.try_div(b.decimal()), "{:?} / {:?}", current, b);
+                expected.ok()
+            }
+            Op::Rem(b) => {
+                let expected = model_div(current, b.decimal(), true);
+                assert_eq!(expected, current.try_rem(b.decimal()), "{:?} % {:?}", current, b);
+                expected.ok()
+            }
+        };
+        if let Some(next) = next {
+            current = next;
+        }
+    }
+});
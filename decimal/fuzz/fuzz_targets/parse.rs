@@ -0,0 +1,23 @@
+//! Feeds arbitrary bytes to `FromStr`. Parsing must never panic, errors must point
+//! inside the input, and anything that parses must survive a trip through `Display`.
+#![no_main]
+
+use decimal::Decimal;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(s) = std::str::from_utf8(data) else { return };
+    match s.parse::<Decimal>() {
+        Ok(d) => {
+            // Display writes every place, so skip scales that would take forever to print.
+            if d.scale <= 4096 {
+                assert_eq!(Ok(d), d.to_string().parse(), "{:?} from {:?}", d, s);
+            }
+        }
+        Err(e) => {
+            if let Some(span) = e.span() {
+                assert!(s.get(span.clone()).is_some(), "span {:?} outside {:?}", span, s);
+            }
+        }
+    }
+});
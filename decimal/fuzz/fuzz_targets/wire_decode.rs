@@ -0,0 +1,20 @@
+//! Feeds arbitrary bytes to `decode` and `decode_key`. Decoding must never panic, and
+//! anything that decodes must encode to something that decodes back to the same decimal.
+#![no_main]
+
+use decimal::Decimal;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok((d, len)) = Decimal::decode(data) {
+        assert!(len <= data.len(), "{:?} claimed {} bytes of {:?}", d, len, data);
+        let mut buf = Vec::new();
+        d.encode(&mut buf);
+        assert_eq!(Ok((d, buf.len())), Decimal::decode(&buf), "{:?} from {:?}", d, data);
+    }
+    if let Ok(d) = Decimal::decode_key(data) {
+        let mut key = Vec::new();
+        d.encode_key(&mut key);
+        assert_eq!(Ok(d), Decimal::decode_key(&key), "{:?} from {:?}", d, data);
+    }
+});
@@ -0,0 +1,55 @@
+//! `quickcheck::Arbitrary` for `Decimal`, enabled by the `arbitrary` feature.
+//!
+//! Generated decimals have up to 18 digits and scales from -4 to 18. Aligning two of them
+//! can still overflow an `i64`, so properties should combine them with the checked
+//! operations (`try_add` and friends) and discard cases that fail. Shrinking tries
+//! smaller scales before smaller unscaled values, since a failure at scale 0 is usually
+//! the easiest to read.
+
+use quickcheck::{Arbitrary, Gen};
+
+use Decimal;
+
+const MAX_DIGITS: u32 = 18;
+const MIN_SCALE: i32 = -4;
+const MAX_SCALE: i32 = 18;
+
+impl Arbitrary for Decimal {
+    fn arbitrary(g: &mut Gen) -> Decimal {
+        let digits = u32::arbitrary(g) % (MAX_DIGITS + 1);
+        let unscaled = i64::arbitrary(g) % 10i64.pow(digits);
+        let scale = MIN_SCALE + (u32::arbitrary(g) % (MAX_SCALE - MIN_SCALE + 1) as u32) as i32;
+        Decimal::new(unscaled, scale)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Decimal>> {
+        let d = *self;
+        let smaller_scales = d.scale.shrink().map(move |scale| Decimal::new(d.unscaled, scale));
+        let smaller_unscaled = d.unscaled.shrink().map(move |unscaled| Decimal::new(unscaled, d.scale));
+        Box::new(smaller_scales.chain(smaller_unscaled))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickcheck::{Arbitrary, Gen};
+    use Decimal;
+
+    #[test]
+    fn generates_decimals_within_bounds() {
+        let mut g = Gen::new(100);
+        for _ in 0..1000 {
+            let d = Decimal::arbitrary(&mut g);
+            assert!(d.unscaled.unsigned_abs() < 1_000_000_000_000_000_000, "{:?}", d);
+            assert!(d.scale >= -4 && d.scale <= 18, "{:?}", d);
+        }
+    }
+
+    #[test]
+    fn shrinks_scale_first() {
+        let shrunk: Vec<Decimal> = Decimal::new(125, 2).shrink().collect();
+        assert_eq!(Decimal::new(125, 0), shrunk[0]);
+        assert!(shrunk.contains(&Decimal::new(0, 2)));
+        assert!(shrunk.iter().all(|d| d.scale.abs() <= 2 && d.unscaled.abs() <= 125));
+    }
+}
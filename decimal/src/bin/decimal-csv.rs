@@ -0,0 +1,311 @@
+//! Checks and totals amount columns in a CSV file, exactly.
+//!
+//! Every cell in the named columns must parse as a `Decimal` (blank cells are skipped),
+//! and each bad one is reported with its row and column. If they all parse, the tool
+//! writes a CSV summary with each column's count, total, minimum and maximum, per group
+//! if `--group-by` names a key column. See `USAGE` for the options.
+
+extern crate csv;
+extern crate decimal;
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::process;
+
+use decimal::{ArithmeticError, Decimal, ParseDecimalError};
+
+const USAGE: &str = "\
+usage: decimal-csv --column NAME [--column NAME]... [--group-by NAME] [FILE]
+
+Reads CSV with a header row from FILE, or stdin if there's none or it's -, and
+summarizes each named amount column exactly.
+
+options:
+    -c, --column NAME     a column of amounts to check and total (repeatable)
+    -g, --group-by NAME   summarize separately for each value of this column
+    -h, --help            print this message";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Options {
+    columns: Vec<String>,
+    group_by: Option<String>,
+    file: Option<String>,
+}
+
+/// Splits the command line into options. `Ok(None)` means help was asked for.
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Option<Options>, String> {
+    let mut options = Options::default();
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "-h" | "--help" => return Ok(None),
+            "-c" | "--column" | "-g" | "--group-by" => {
+                let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                if arg.starts_with("-c") || arg.starts_with("--c") {
+                    options.columns.push(value);
+                } else {
+                    options.group_by = Some(value);
+                }
+            }
+            "-" => options.file = None,
+            _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+            _ if options.file.is_some() => return Err("more than one file given".to_string()),
+            _ => options.file = Some(arg),
+        }
+    }
+    if options.columns.is_empty() {
+        return Err("no --column given".to_string());
+    }
+    Ok(Some(options))
+}
+
+/// The count, total and range of one column's values.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Stats {
+    count: u64,
+    total: Option<Decimal>,
+    min: Option<Decimal>,
+    max: Option<Decimal>,
+}
+
+impl Stats {
+    fn add(&mut self, value: Decimal) -> Result<(), ArithmeticError> {
+        self.total = Some(match self.total {
+            Some(total) => total.try_add(value)?,
+            None => value,
+        });
+        if self.min.is_none_or(|min| value < min) {
+            self.min = Some(value);
+        }
+        if self.max.is_none_or(|max| value > max) {
+            self.max = Some(value);
+        }
+        self.count += 1;
+        Ok(())
+    }
+}
+
+/// Stats for each group (just `""` when not grouping), each with one `Stats` per column.
+type Summary = BTreeMap<String, Vec<Stats>>;
+
+#[derive(Debug)]
+enum Error {
+    Csv(csv::Error),
+    MissingColumn(String),
+    Cell { row: u64, column: String, value: String, error: ParseDecimalError },
+    Overflow { row: u64, column: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Csv(ref e) => write!(f, "{}", e),
+            Error::MissingColumn(ref name) => write!(f, "no column named '{}'", name),
+            Error::Cell { row, ref column, ref value, ref error } => {
+                // Line the caret up under the value, past this prefix.
+                let prefix = format!("row {}, column {}: ", row, column);
+                let indent = format!("\n{}", " ".repeat(prefix.chars().count()));
+                write!(f, "{}{}", prefix, error.annotate(value).replace('\n', &indent))
+            }
+            Error::Overflow { row, ref column } =>
+                write!(f, "row {}, column {}: total overflowed", row, column),
+        }
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Error {
+        Error::Csv(e)
+    }
+}
+
+/// Reads every row, returning the summary or every problem found. Problems with the
+/// file as a whole stop it early; bad cells don't.
+fn summarize<R: Read>(input: R, options: &Options) -> Result<Summary, Vec<Error>> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(input);
+    let headers = reader.headers().map_err(|e| vec![Error::from(e)])?.clone();
+    let index_of = |name: &String| headers.iter().position(|h| h == name)
+        .ok_or_else(|| vec![Error::MissingColumn(name.clone())]);
+    let columns = options.columns.iter().map(&index_of).collect::<Result<Vec<_>, _>>()?;
+    let key = options.group_by.as_ref().map(&index_of).transpose()?;
+
+    let mut summary = Summary::new();
+    if key.is_none() {
+        // Report the columns even if there are no rows.
+        summary.insert(String::new(), vec![Stats::default(); columns.len()]);
+    }
+    let mut errors = vec![];
+    for record in reader.records() {
+        let record = record.map_err(|e| vec![Error::from(e)])?;
+        let row = record.position().map_or(0, |p| p.line());
+        let group = key.and_then(|k| record.get(k)).unwrap_or("");
+        let stats = summary.entry(group.to_string())
+            .or_insert_with(|| vec![Stats::default(); columns.len()]);
+        for (i, &column) in columns.iter().enumerate() {
+            let value = record.get(column).unwrap_or("");
+            if value.is_empty() {
+                continue;
+            }
+            let name = &options.columns[i];
+            let result = match value.parse() {
+                Ok(d) => stats[i].add(d)
+                    .map_err(|_| Error::Overflow { row, column: name.clone() }),
+                Err(error) => Err(Error::Cell { row, column: name.clone(),
+                                                value: value.to_string(), error }),
+            };
+            errors.extend(result.err());
+        }
+    }
+    if errors.is_empty() { Ok(summary) } else { Err(errors) }
+}
+
+/// Writes the summary as CSV, one row per group and column.
+fn write_summary<W: io::Write>(output: W, summary: &Summary, options: &Options)
+                               -> csv::Result<()> {
+    let mut writer = csv::Writer::from_writer(output);
+    let show = |d: Option<Decimal>| d.map_or_else(String::new, |d| d.to_string());
+    let mut header = vec!["column", "count", "total", "min", "max"];
+    if let Some(ref key) = options.group_by {
+        header.insert(0, key);
+    }
+    writer.write_record(&header)?;
+    for (group, stats) in summary {
+        for (name, s) in options.columns.iter().zip(stats) {
+            let total = s.total.unwrap_or(Decimal::new(0, 0));
+            let mut row = vec![name.clone(), s.count.to_string(), total.to_string(),
+                               show(s.min), show(s.max)];
+            if options.group_by.is_some() {
+                row.insert(0, group.clone());
+            }
+            writer.write_record(&row)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(message) => {
+            eprintln!("decimal-csv: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+    let input: Box<dyn Read> = match options.file {
+        Some(ref path) => match File::open(path) {
+            Ok(file) => Box::new(file),
+            Err(e) => {
+                eprintln!("decimal-csv: {}: {}", path, e);
+                process::exit(2);
+            }
+        },
+        None => Box::new(io::stdin()),
+    };
+    let summary = summarize(input, &options).unwrap_or_else(|errors| {
+        for e in errors {
+            eprintln!("{}", e);
+        }
+        process::exit(1);
+    });
+    if let Err(e) = write_summary(io::stdout(), &summary, &options) {
+        eprintln!("decimal-csv: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_args, summarize, write_summary, Options};
+
+    fn options(columns: &[&str], group_by: Option<&str>) -> Options {
+        Options {
+            columns: columns.iter().map(|s| s.to_string()).collect(),
+            group_by: group_by.map(|s| s.to_string()),
+            file: None,
+        }
+    }
+
+    fn run(input: &str, options: &Options) -> Result<String, Vec<String>> {
+        let summary = summarize(input.as_bytes(), options)
+            .map_err(|errors| errors.iter().map(|e| e.to_string()).collect::<Vec<_>>())?;
+        let mut output = vec![];
+        write_summary(&mut output, &summary, options).unwrap();
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    const LEDGER: &str = "\
+region,amount,fee
+east,10.10,0.25
+west, 5,
+east,-0.1,1E+1
+west,2.005,0.10
+";
+
+    #[test]
+    fn totals_columns_exactly() {
+        assert_eq!(Ok("column,count,total,min,max\n\
+                       amount,4,17.005,-0.1,10.10\n\
+                       fee,3,10.35,0.10,1E+1\n".to_string()),
+                   run(LEDGER, &options(&["amount", "fee"], None)));
+    }
+
+    #[test]
+    fn totals_by_group() {
+        assert_eq!(Ok("region,column,count,total,min,max\n\
+                       east,amount,2,10.00,-0.1,10.10\n\
+                       east,fee,2,10.25,0.25,1E+1\n\
+                       west,amount,2,7.005,2.005,5\n\
+                       west,fee,1,0.10,0.10,0.10\n".to_string()),
+                   run(LEDGER, &options(&["amount", "fee"], Some("region"))));
+    }
+
+    #[test]
+    fn empty_columns_total_zero() {
+        assert_eq!(Ok("column,count,total,min,max\nfee,0,0,,\n".to_string()),
+                   run("fee\n\n", &options(&["fee"], None)));
+    }
+
+    #[test]
+    fn reports_every_bad_cell() {
+        let input = "id,amount\n1,12.3x\n2,4\n3,1.2.3\n4,-\n";
+        let indent = " ".repeat("row 2, column amount: ".len());
+        assert_eq!(Err(vec![format!("row 2, column amount: 12.3x\n{}    ^ invalid character 'x' \
+                                     found at index 4", indent),
+                            format!("row 4, column amount: 1.2.3\n{}   ^ invalid character '.' \
+                                     found at index 3", indent),
+                            format!("row 5, column amount: -\n{}no digits found in decimal",
+                                    indent)]),
+                   run(input, &options(&["amount"], None)));
+    }
+
+    #[test]
+    fn reports_overflow_and_missing_columns() {
+        assert_eq!(Err(vec!["row 3, column n: total overflowed".to_string()]),
+                   run("n\n9223372036854775807\n1\n", &options(&["n"], None)));
+        assert_eq!(Err(vec!["no column named 'amount'".to_string()]),
+                   run("id,amt\n1,2\n", &options(&["amount"], None)));
+        assert_eq!(Err(vec!["no column named 'region'".to_string()]),
+                   run("amount\n1\n", &options(&["amount"], Some("region"))));
+    }
+
+    #[test]
+    fn parses_options() {
+        let args = |a: &[&str]| parse_args(a.iter().map(|s| s.to_string()));
+        let mut expected = options(&["a", "b"], Some("k"));
+        assert_eq!(Ok(Some(expected.clone())), args(&["-c", "a", "--group-by", "k", "--column", "b"]));
+        expected.file = Some("x.csv".to_string());
+        assert_eq!(Ok(Some(expected)), args(&["x.csv", "-c", "a", "-g", "k", "-c", "b"]));
+        assert_eq!(Ok(None), args(&["-c", "a", "--help"]));
+        assert_eq!(Err("no --column given".to_string()), args(&["x.csv"]));
+        assert_eq!(Err("--column needs a value".to_string()), args(&["--column"]));
+        assert_eq!(Err("more than one file given".to_string()), args(&["-c", "a", "x", "y"]));
+        assert_eq!(Err("unknown option '--sum'".to_string()), args(&["--sum", "a"]));
+    }
+}
@@ -0,0 +1,380 @@
+//! Many `Decimal`s sharing one scale, such as a column of prices read from a file.
+//!
+//! A `DecimalColumn` stores only the unscaled `i64` values, so its operations work on
+//! plain integers: a sum is one pass of `i128` additions, and adding two columns
+//! aligns their scales once rather than once per element.
+//!
+//! # Examples
+//! ```
+//! # use std::convert::TryFrom;
+//! # use decimal::{Decimal, RoundingMode};
+//! # use decimal::column::DecimalColumn;
+//! let prices = DecimalColumn::try_from(vec![Decimal::new(1999, 2), Decimal::new(5, 0),
+//!                                           Decimal::new(1250, 2)]).unwrap();
+//! assert_eq!(2, prices.scale());
+//! assert_eq!(&[1999, 500, 1250], prices.unscaled());
+//! assert_eq!(Ok(Decimal::new(3749, 2)), prices.sum());
+//!
+//! let quantities = DecimalColumn::from_unscaled(vec![3, 1, 2], 0);
+//! let totals = prices.try_mul(&quantities).unwrap();
+//! assert_eq!(Ok(Decimal::new(8997, 2)), totals.sum());
+//!
+//! let over_ten = prices.greater_than(Decimal::new(10, 0));
+//! assert_eq!(vec![true, false, true], over_ten);
+//! assert_eq!(vec![Decimal::new(20, 0), Decimal::new(13, 0)],
+//!            prices.select(&over_ten).rescale(0, RoundingMode::HalfUp).to_vec());
+//! ```
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::iter::FusedIterator;
+use std::slice;
+
+use {checked_upscale, downscale_rounded, scale_diff, ArithmeticError, Decimal, RoundingMode};
+
+/// A sequence of `Decimal`s stored as unscaled values with a single scale.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct DecimalColumn {
+    unscaled: Vec<i64>,
+    scale: i32,
+}
+
+impl DecimalColumn {
+    /// An empty column of the given scale.
+    pub fn new(scale: i32) -> DecimalColumn {
+        DecimalColumn { unscaled: vec![], scale }
+    }
+
+    /// A column of `unscaled` values, each with the given scale.
+    pub fn from_unscaled(unscaled: Vec<i64>, scale: i32) -> DecimalColumn {
+        DecimalColumn { unscaled, scale }
+    }
+
+    pub fn scale(&self) -> i32 {
+        self.scale
+    }
+
+    pub fn unscaled(&self) -> &[i64] {
+        &self.unscaled
+    }
+
+    pub fn len(&self) -> usize {
+        self.unscaled.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.unscaled.is_empty()
+    }
+
+    /// The value at `index`, if there is one.
+    pub fn get(&self, index: usize) -> Option<Decimal> {
+        self.unscaled.get(index).map(|&n| Decimal::new(n, self.scale))
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { unscaled: self.unscaled.iter(), scale: self.scale }
+    }
+
+    pub fn to_vec(&self) -> Vec<Decimal> {
+        self.iter().collect()
+    }
+
+    /// Appends `value`, brought to the column's scale. Fails with `Overflow` if that
+    /// doesn't fit, or `Inexact` if it would drop nonzero places.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{ArithmeticError, Decimal};
+    /// # use decimal::column::DecimalColumn;
+    /// let mut column = DecimalColumn::new(2);
+    /// column.push(Decimal::new(3, 0)).unwrap();
+    /// column.push(Decimal::new(1500, 3)).unwrap();
+    /// assert_eq!(Err(ArithmeticError::Inexact), column.push(Decimal::new(1501, 3)));
+    /// assert_eq!(&[300, 150], column.unscaled());
+    /// ```
+    pub fn push(&mut self, value: Decimal) -> Result<(), ArithmeticError> {
+        let unscaled = if value.scale <= self.scale {
+            checked_upscale(value.unscaled, scale_diff(self.scale, value.scale))
+                .ok_or(ArithmeticError::Overflow)?
+        } else {
+            // Only 0 has 19 or more trailing zeros to drop.
+            match 10i64.checked_pow(scale_diff(value.scale, self.scale)) {
+                Some(factor) if value.unscaled % factor == 0 => value.unscaled / factor,
+                None if value.unscaled == 0 => 0,
+                _ => return Err(ArithmeticError::Inexact),
+            }
+        };
+        self.unscaled.push(unscaled);
+        Ok(())
+    }
+
+    /// The sum of all values, at the column's scale. Fails only if the total doesn't fit
+    /// in a `Decimal`.
+    pub fn sum(&self) -> Result<Decimal, ArithmeticError> {
+        let total: i128 = self.unscaled.iter().map(|&n| n as i128).sum();
+        i64::try_from(total).map(|unscaled| Decimal::new(unscaled, self.scale))
+            .map_err(|_| ArithmeticError::Overflow)
+    }
+
+    /// Adds the columns element by element. The result has the wider of the two scales.
+    ///
+    /// Panics if the columns have different lengths.
+    pub fn try_add(&self, other: &DecimalColumn) -> Result<DecimalColumn, ArithmeticError> {
+        assert_same_len(self, other);
+        let scale = self.scale.max(other.scale);
+        let a = self.upscaled(scale)?;
+        let b = other.upscaled(scale)?;
+        let unscaled = a.iter().zip(&b)
+            .map(|(&x, &y)| x.checked_add(y).ok_or(ArithmeticError::Overflow))
+            .collect::<Result<_, _>>()?;
+        Ok(DecimalColumn { unscaled, scale })
+    }
+
+    /// Multiplies the columns element by element. As with `*`, the result's scale is the
+    /// sum of the two scales.
+    ///
+    /// Panics if the columns have different lengths.
+    pub fn try_mul(&self, other: &DecimalColumn) -> Result<DecimalColumn, ArithmeticError> {
+        assert_same_len(self, other);
+        let scale = self.scale.checked_add(other.scale).ok_or(ArithmeticError::ScaleOverflow)?;
+        let unscaled = self.unscaled.iter().zip(&other.unscaled)
+            .map(|(&x, &y)| x.checked_mul(y).ok_or(ArithmeticError::Overflow))
+            .collect::<Result<_, _>>()?;
+        Ok(DecimalColumn { unscaled, scale })
+    }
+
+    /// Every value brought to `scale`, rounding according to `mode` when that drops
+    /// places. Only gaining places can fail, with `Overflow`.
+    pub fn try_rescale(&self, scale: i32, mode: RoundingMode)
+                       -> Result<DecimalColumn, ArithmeticError> {
+        if scale >= self.scale {
+            return Ok(DecimalColumn { unscaled: self.upscaled(scale)?, scale });
+        }
+        let down_by = scale_diff(self.scale, scale);
+        let unscaled = self.unscaled.iter()
+            .map(|&n| downscale_rounded(n as i128, down_by, mode) as i64)
+            .collect();
+        Ok(DecimalColumn { unscaled, scale })
+    }
+
+    /// Like `try_rescale`, but panics if a value doesn't fit at the new scale.
+    pub fn rescale(&self, scale: i32, mode: RoundingMode) -> DecimalColumn {
+        self.try_rescale(scale, mode).expect("attempt to rescale with overflow")
+    }
+
+    /// For each value, whether it is less than `value`.
+    pub fn less_than(&self, value: Decimal) -> Vec<bool> {
+        self.mask(value, |ordering| ordering == Ordering::Less)
+    }
+
+    /// For each value, whether it is greater than `value`.
+    pub fn greater_than(&self, value: Decimal) -> Vec<bool> {
+        self.mask(value, |ordering| ordering == Ordering::Greater)
+    }
+
+    /// For each value, whether it is numerically equal to `value`, whatever the scales.
+    pub fn equal_to(&self, value: Decimal) -> Vec<bool> {
+        self.mask(value, |ordering| ordering == Ordering::Equal)
+    }
+
+    /// The values whose entries in `mask` are `true`, e.g. from `greater_than`.
+    ///
+    /// Panics if `mask` isn't the same length as the column.
+    pub fn select(&self, mask: &[bool]) -> DecimalColumn {
+        assert_eq!(self.len(), mask.len(), "mask length differs from decimal column length");
+        let unscaled = self.unscaled.iter().zip(mask)
+            .filter(|&(_, &keep)| keep)
+            .map(|(&n, _)| n)
+            .collect();
+        DecimalColumn { unscaled, scale: self.scale }
+    }
+
+    fn mask<F: Fn(Ordering) -> bool>(&self, value: Decimal, f: F) -> Vec<bool> {
+        // Bring whichever side has the smaller scale up to the other's, once.
+        if value.scale <= self.scale {
+            let factor = pow10(scale_diff(self.scale, value.scale));
+            self.unscaled.iter().map(|&n| f(cmp_upscaled(value.unscaled, factor, n).reverse()))
+                .collect()
+        } else {
+            let factor = pow10(scale_diff(value.scale, self.scale));
+            self.unscaled.iter().map(|&n| f(cmp_upscaled(n, factor, value.unscaled))).collect()
+        }
+    }
+
+    fn upscaled(&self, scale: i32) -> Result<Vec<i64>, ArithmeticError> {
+        let up_by = scale_diff(scale, self.scale);
+        self.unscaled.iter()
+            .map(|&n| checked_upscale(n, up_by).ok_or(ArithmeticError::Overflow))
+            .collect()
+    }
+}
+
+fn assert_same_len(a: &DecimalColumn, b: &DecimalColumn) {
+    assert_eq!(a.len(), b.len(), "decimal columns have different lengths");
+}
+
+/// 10^`exp`, or `None` if that's beyond an `i128`.
+fn pow10(exp: u32) -> Option<i128> {
+    10i128.checked_pow(exp)
+}
+
+/// Compares `a * factor` with `b`, where a missing factor is too big to represent.
+fn cmp_upscaled(a: i64, factor: Option<i128>, b: i64) -> Ordering {
+    if a == 0 {
+        return 0.cmp(&b);
+    }
+    match factor.and_then(|factor| (a as i128).checked_mul(factor)) {
+        Some(scaled) => scaled.cmp(&(b as i128)),
+        // A nonzero value scaled that far is beyond any i64.
+        None => a.cmp(&0),
+    }
+}
+
+/// Collects decimals into a column with the widest of their scales (0 if there are
+/// none). Fails with `Overflow` if a value doesn't fit at that scale.
+impl TryFrom<Vec<Decimal>> for DecimalColumn {
+    type Error = ArithmeticError;
+
+    fn try_from(values: Vec<Decimal>) -> Result<DecimalColumn, ArithmeticError> {
+        let scale = values.iter().map(|d| d.scale).max().unwrap_or(0);
+        let mut column = DecimalColumn { unscaled: Vec::with_capacity(values.len()), scale };
+        for value in values {
+            column.push(value)?;
+        }
+        Ok(column)
+    }
+}
+
+impl From<DecimalColumn> for Vec<Decimal> {
+    fn from(column: DecimalColumn) -> Vec<Decimal> {
+        column.to_vec()
+    }
+}
+
+impl<'a> IntoIterator for &'a DecimalColumn {
+    type Item = Decimal;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the values in a `DecimalColumn`. Created by `DecimalColumn::iter`.
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    unscaled: slice::Iter<'a, i64>,
+    scale: i32,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Decimal;
+
+    fn next(&mut self) -> Option<Decimal> {
+        self.unscaled.next().map(|&n| Decimal::new(n, self.scale))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.unscaled.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Iter<'a> {
+    fn next_back(&mut self) -> Option<Decimal> {
+        self.unscaled.next_back().map(|&n| Decimal::new(n, self.scale))
+    }
+}
+
+impl<'a> ExactSizeIterator for Iter<'a> {}
+
+impl<'a> FusedIterator for Iter<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::DecimalColumn;
+    use {ArithmeticError, Decimal};
+
+    fn column(unscaled: &[i64], scale: i32) -> DecimalColumn {
+        DecimalColumn::from_unscaled(unscaled.to_vec(), scale)
+    }
+
+    #[test]
+    fn converts_to_and_from_vecs() {
+        let values = vec![Decimal::new(15, 1), Decimal::new(-2, 0), Decimal::new(3, -2)];
+        let c = DecimalColumn::try_from(values).unwrap();
+        assert_eq!(column(&[15, -20, 3000], 1), c);
+        assert_eq!(vec![Decimal::new(15, 1), Decimal::new(-20, 1), Decimal::new(3000, 1)],
+                   Vec::from(c.clone()));
+        assert_eq!(Some(Decimal::new(-20, 1)), c.get(1));
+        assert_eq!(None, c.get(3));
+        assert_eq!(vec![Decimal::new(3000, 1)], c.iter().rev().take(1).collect::<Vec<_>>());
+        assert_eq!(DecimalColumn::new(0), DecimalColumn::try_from(vec![]).unwrap());
+        assert_eq!(Err(ArithmeticError::Overflow),
+                   DecimalColumn::try_from(vec![Decimal::new(i64::MAX, 0), Decimal::new(1, 1)]));
+    }
+
+    #[test]
+    fn push_keeps_the_column_scale() {
+        let mut c = DecimalColumn::new(-1);
+        assert_eq!(Ok(()), c.push(Decimal::new(120, 0)));
+        assert_eq!(Ok(()), c.push(Decimal::new(0, 40)));
+        assert_eq!(Ok(()), c.push(Decimal::new(7, -3)));
+        assert_eq!(Err(ArithmeticError::Inexact), c.push(Decimal::new(1, 0)));
+        assert_eq!(Err(ArithmeticError::Inexact), c.push(Decimal::new(1, 40)));
+        assert_eq!(Err(ArithmeticError::Overflow), c.push(Decimal::new(i64::MAX, -2)));
+        assert_eq!(column(&[12, 0, 700], -1), c);
+    }
+
+    #[test]
+    fn sums() {
+        assert_eq!(Ok(Decimal::new(0, 3)), DecimalColumn::new(3).sum());
+        assert_eq!(Ok(Decimal::new(i64::MAX - 1, 0)),
+                   column(&[i64::MAX, i64::MAX, -i64::MAX, -1], 0).sum());
+        assert_eq!(Err(ArithmeticError::Overflow), column(&[i64::MAX, 1], 0).sum());
+    }
+
+    #[test]
+    fn adds_and_multiplies_elementwise() {
+        let a = column(&[100, -250, 5], 2);
+        let b = column(&[1, 2, 3], 0);
+        assert_eq!(Ok(column(&[200, -50, 305], 2)), a.try_add(&b));
+        assert_eq!(Ok(column(&[100, -500, 15], 2)), a.try_mul(&b));
+        assert_eq!(Err(ArithmeticError::Overflow), column(&[i64::MAX], 0).try_add(&column(&[1], 0)));
+        assert_eq!(Err(ArithmeticError::Overflow), column(&[i64::MAX], 0).try_add(&column(&[0], 1)));
+        assert_eq!(Err(ArithmeticError::Overflow), column(&[i64::MAX], 0).try_mul(&column(&[2], 0)));
+        assert_eq!(Err(ArithmeticError::ScaleOverflow), column(&[1], i32::MAX).try_mul(&column(&[1], 1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "decimal columns have different lengths")]
+    fn elementwise_operations_need_equal_lengths() {
+        let _ = column(&[1, 2], 0).try_add(&column(&[1], 0));
+    }
+
+    #[test]
+    fn rescales() {
+        use RoundingMode::*;
+        let c = column(&[125, -125, 135, 1], 2);
+        assert_eq!(column(&[12, -12, 14, 0], 1), c.rescale(1, HalfEven));
+        assert_eq!(column(&[13, -13, 14, 1], 1), c.rescale(1, Up));
+        assert_eq!(column(&[2, -1, 2, 1], 0), c.rescale(0, Ceiling));
+        assert_eq!(column(&[0, 0, 0, 0], -40), c.rescale(-40, HalfUp));
+        assert_eq!(column(&[12500, -12500, 13500, 100], 4), c.rescale(4, Down));
+        assert_eq!(Err(ArithmeticError::Overflow), c.try_rescale(20, Down));
+    }
+
+    #[test]
+    fn comparison_masks() {
+        let c = column(&[-5, 0, 5, 10, i64::MAX], 1);
+        assert_eq!(vec![true, true, false, false, false], c.less_than(Decimal::new(5, 1)));
+        assert_eq!(vec![false, false, true, false, false], c.equal_to(Decimal::new(50, 2)));
+        assert_eq!(vec![false, false, false, true, true], c.greater_than(Decimal::new(6, 1)));
+        assert_eq!(vec![false, false, false, false, true], c.greater_than(Decimal::new(1, -3)));
+        assert_eq!(vec![true, true, true, true, true], c.less_than(Decimal::new(1, -40)));
+        assert_eq!(vec![false, false, true, true, true], c.greater_than(Decimal::new(1, 40)));
+        assert_eq!(vec![false, true, false, false, false], c.equal_to(Decimal::new(0, -40)));
+        assert_eq!(column(&[10, i64::MAX], 1), c.select(&c.greater_than(Decimal::new(5, 1))));
+    }
+}
@@ -0,0 +1,262 @@
+//! Abbreviated amounts for dashboards, such as `1.2K` and `3.4M`.
+//!
+//! `Decimal::to_compact` rounds to a number of significant digits and picks a suffix for
+//! the power of a thousand, and `Decimal::from_compact` reads such abbreviations back as
+//! exact `Decimal`s. Suffixes always stand for powers of 1000, never 1024.
+//!
+//! # Examples
+//! ```
+//! # use decimal::{Decimal, RoundingMode};
+//! # use decimal::compact::{CompactFormat, Suffixes};
+//! let revenue = Decimal::new(123456789, 2);
+//! assert_eq!("1.23M", revenue.to_compact(CompactFormat::default()));
+//! assert_eq!("1.2M", revenue.to_compact(CompactFormat::new(Suffixes::Short, 2, RoundingMode::Down)));
+//!
+//! let bytes = Decimal::new(2_500_000_000, 0);
+//! assert_eq!("2.5G", bytes.to_compact(CompactFormat::new(Suffixes::Si, 3, RoundingMode::HalfEven)));
+//!
+//! assert_eq!(Ok(Decimal::new(1200, 0)), Decimal::from_compact("1.2K"));
+//! assert_eq!(Ok(Decimal::new(-3405, 0)), Decimal::from_compact("-3.405k"));
+//! ```
+
+use std::convert::TryFrom;
+
+use {checked_upscale, Decimal, DecimalErrorKind, ParseDecimalError, RoundingMode};
+
+/// Which suffixes stand for the powers of a thousand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Suffixes {
+    /// Thousand, million, billion and trillion: `K`, `M`, `B` and `T`.
+    Short,
+    /// The SI prefixes kilo, mega, giga, tera and peta: `k`, `M`, `G`, `T` and `P`.
+    Si,
+}
+
+impl Suffixes {
+    /// The suffix for each power of a thousand, starting with none for 1000^0.
+    fn symbols(&self) -> &'static [&'static str] {
+        match *self {
+            Suffixes::Short => &["", "K", "M", "B", "T"],
+            Suffixes::Si => &["", "k", "M", "G", "T", "P"],
+        }
+    }
+}
+
+/// How `Decimal::to_compact` abbreviates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CompactFormat {
+    pub suffixes: Suffixes,
+    /// How many significant digits to round to, which must be at least 1. Trailing zeros
+    /// after the decimal point are dropped, so there may be fewer.
+    pub significant_digits: u32,
+    pub mode: RoundingMode,
+}
+
+impl CompactFormat {
+    pub fn new(suffixes: Suffixes, significant_digits: u32, mode: RoundingMode) -> CompactFormat {
+        CompactFormat { suffixes, significant_digits, mode }
+    }
+}
+
+/// `Short` suffixes, three significant digits and `HalfEven` rounding.
+impl Default for CompactFormat {
+    fn default() -> CompactFormat {
+        CompactFormat::new(Suffixes::Short, 3, RoundingMode::HalfEven)
+    }
+}
+
+impl Decimal {
+    /// Abbreviates this decimal, e.g. as `1.23M`, rounding it to `format`'s significant
+    /// digits. Amounts under a thousand get no suffix, and amounts past the largest
+    /// suffix are written in multiples of it, e.g. `1500T`.
+    ///
+    /// Panics if `format.significant_digits` is zero.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::Decimal;
+    /// # use decimal::compact::CompactFormat;
+    /// let format = CompactFormat::default();
+    /// assert_eq!("999", Decimal::new(999, 0).to_compact(format));
+    /// // Rounding can carry into the next suffix.
+    /// assert_eq!("1M", Decimal::new(999_999, 0).to_compact(format));
+    /// assert_eq!("-0.0123", Decimal::new(-123456, 7).to_compact(format));
+    /// ```
+    pub fn to_compact(&self, format: CompactFormat) -> String {
+        assert!(format.significant_digits > 0, "compact format needs at least 1 significant digit");
+        let symbols = format.suffixes.symbols();
+        // Rounding first means a carry, as from 999.9 to 1000, picks the next suffix.
+        let places = format.significant_digits as i64 - 1 - exponent(*self);
+        let rounded = if places < self.scale as i64 {
+            self.round(places.max(i32::MIN as i64) as i32, format.mode)
+        } else {
+            *self
+        };
+        let power = if rounded.unscaled == 0 {
+            0
+        } else {
+            exponent(rounded).div_euclid(3).max(0).min(symbols.len() as i64 - 1) as usize
+        };
+        // Dividing by a power of a thousand just moves the point.
+        let value = Decimal::new(rounded.unscaled, rounded.scale + 3 * power as i32);
+        format!("{}{}", plain(value), symbols[power])
+    }
+
+    /// Parses an abbreviated decimal like `1.2K`, `3.4M` or `5G`, accepting either kind
+    /// of `Suffixes`, or a decimal with no suffix. The result is exact, and has a scale
+    /// of zero rather than a negative one if it's a whole number that fits.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::Decimal;
+    /// assert_eq!(Ok(Decimal::new(2_500_000_000, 0)), Decimal::from_compact("2.5B"));
+    /// assert_eq!(Ok(Decimal::new(2500, 2)), Decimal::from_compact("0.02500K"));
+    /// assert_eq!(Ok(Decimal::new(123, 4)), Decimal::from_compact("0.0123"));
+    /// assert!(Decimal::from_compact("1.2X").is_err());
+    /// ```
+    pub fn from_compact(s: &str) -> Result<Decimal, ParseDecimalError> {
+        let (number, power) = match s.chars().last() {
+            Some('K') | Some('k') => (&s[..s.len() - 1], 1),
+            Some('M') => (&s[..s.len() - 1], 2),
+            Some('B') | Some('G') => (&s[..s.len() - 1], 3),
+            Some('T') => (&s[..s.len() - 1], 4),
+            Some('P') => (&s[..s.len() - 1], 5),
+            _ => (s, 0),
+        };
+        let d: Decimal = number.parse()?;
+        let scale = d.scale.checked_sub(3 * power)
+            .ok_or_else(|| ParseDecimalError::new(DecimalErrorKind::ExponentOutOfRange))?;
+        Ok(whole(Decimal::new(d.unscaled, scale)))
+    }
+}
+
+/// The exponent of `d`'s leading digit, e.g. 2 for 123.4 and -2 for 0.012.
+fn exponent(d: Decimal) -> i64 {
+    d.precision() as i64 - 1 - d.scale as i64
+}
+
+/// `d` with trailing zeros after the point dropped, and otherwise as `whole`.
+fn plain(mut d: Decimal) -> Decimal {
+    while d.scale > 0 && d.unscaled % 10 == 0 {
+        d = Decimal::new(d.unscaled / 10, d.scale - 1);
+    }
+    whole(d)
+}
+
+/// `d` with a negative scale raised to zero, if the result fits, so it displays without
+/// an exponent.
+fn whole(d: Decimal) -> Decimal {
+    if d.scale < 0 {
+        if let Some(unscaled) = u32::try_from(-(d.scale as i64)).ok()
+                .and_then(|up_by| checked_upscale(d.unscaled, up_by)) {
+            return Decimal::new(unscaled, 0);
+        }
+    }
+    d
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::{CompactFormat, Suffixes};
+    use {Decimal, DecimalErrorKind, RoundingMode};
+    use RoundingMode::*;
+
+    fn d(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    fn compact(s: &str, digits: u32, mode: RoundingMode) -> String {
+        d(s).to_compact(CompactFormat::new(Suffixes::Short, digits, mode))
+    }
+
+    #[test]
+    fn picks_suffixes() {
+        let format = CompactFormat::default();
+        let cases = [("0", "0"), ("0.00", "0"), ("7", "7"), ("12.50", "12.5"), ("999", "999"),
+                     ("1000", "1K"), ("1200", "1.2K"), ("-1234", "-1.23K"), ("15300", "15.3K"),
+                     ("999499", "999K"), ("3.4E+6", "3.4M"), ("1E+9", "1B"), ("4.56789E+12", "4.57T"),
+                     ("1.5E+15", "1500T"), ("1E+30", "1000000000000000000T"), ("1E+40", "1E+28T")];
+        for &(input, expected) in cases.iter() {
+            assert_eq!(expected, d(input).to_compact(format), "formatting {}", input);
+        }
+        let si = CompactFormat::new(Suffixes::Si, 3, HalfEven);
+        let cases = [("1200", "1.2k"), ("3.4E+6", "3.4M"), ("2.5E+9", "2.5G"), ("1E+12", "1T"),
+                     ("6E+15", "6P"), ("7E+18", "7000P")];
+        for &(input, expected) in cases.iter() {
+            assert_eq!(expected, d(input).to_compact(si), "formatting {}", input);
+        }
+    }
+
+    #[test]
+    fn rounds_to_significant_digits() {
+        assert_eq!("1.2K", compact("1250", 2, HalfEven));
+        assert_eq!("1.3K", compact("1250", 2, HalfUp));
+        assert_eq!("1K", compact("1250", 1, HalfUp));
+        assert_eq!("120", compact("123", 2, HalfUp));
+        assert_eq!("130", compact("123", 2, Ceiling));
+        assert_eq!("-130", compact("-123", 2, Floor));
+        assert_eq!("120K", compact("123456", 2, Down));
+        assert_eq!("1.23456K", compact("1234.56", 10, HalfUp));
+        assert_eq!("0.00012", compact("0.000123", 2, HalfUp));
+        assert_eq!("1", compact("0.96", 1, HalfUp));
+    }
+
+    #[test]
+    fn rounding_carries_into_the_next_suffix() {
+        assert_eq!("1K", compact("999.5", 3, HalfUp));
+        assert_eq!("1M", compact("999999", 3, HalfUp));
+        assert_eq!("-1B", compact("-999999999", 2, HalfEven));
+        assert_eq!("999K", compact("999999", 3, Down));
+        assert_eq!("1000T", compact("999.9E+12", 3, HalfUp));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 1 significant digit")]
+    fn zero_significant_digits_panics() {
+        compact("1", 0, HalfUp);
+    }
+
+    #[test]
+    fn parses_abbreviations() {
+        let cases = [("1.2K", "1200"), ("1.2k", "1200"), ("-3.4M", "-3400000"), ("5B", "5000000000"),
+                     ("5G", "5000000000"), ("0.5T", "500000000000"), ("2P", "2000000000000000"),
+                     ("1.23456K", "1234.56"), ("12.50", "12.50"), ("1E+3K", "1000000"),
+                     ("9.3E+18K", "9.3E+21")];
+        for &(input, expected) in cases.iter() {
+            assert_eq!(Ok(d(expected)), Decimal::from_compact(input), "parsing {}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_abbreviations() {
+        let kind = |s: &str| Decimal::from_compact(s).unwrap_err().kind().clone();
+        assert_eq!(DecimalErrorKind::Empty, kind("K"));
+        assert_eq!(DecimalErrorKind::Empty, kind(""));
+        assert_eq!(DecimalErrorKind::ExponentOutOfRange, kind("1E-2147483648K"));
+        match kind("1.2X") {
+            DecimalErrorKind::InvalidChar { character: 'X', .. } => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match kind("1.2KK") {
+            DecimalErrorKind::InvalidChar { character: 'K', .. } => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match kind("1.2m") {
+            DecimalErrorKind::InvalidChar { character: 'm', .. } => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn formatted_amounts_parse_back() {
+        for s in &["0", "-7", "1234", "1234.5678", "98765432", "-1.5E+13", "0.000123"] {
+            for &suffixes in &[Suffixes::Short, Suffixes::Si] {
+                let text = d(s).to_compact(CompactFormat::new(suffixes, 19, HalfEven));
+                let parsed = Decimal::from_compact(&text).unwrap();
+                assert_eq!(d(s).partial_cmp(&parsed), Some(Ordering::Equal), "{}", text);
+            }
+        }
+    }
+}
@@ -0,0 +1,449 @@
+//! Converting amounts between currencies.
+//!
+//! A `RateTable` holds exchange rates, each saying how many units of one currency buy
+//! one unit of another. Converting looks for a rate from the source to the target, then
+//! for the inverse of one from the target to the source, then triangulates through the
+//! table's base currency. However the rate is found, the amount is multiplied and
+//! divided exactly and rounded just once, to the target currency's minor units (or a
+//! scale of the caller's choosing).
+//!
+//! # Examples
+//! ```
+//! # use decimal::{Decimal, RoundingMode};
+//! # use decimal::exchange::{Currency, RateTable};
+//! let usd: Currency = "USD".parse().unwrap();
+//! let rates = RateTable::from_csv(usd, "\
+//! from,to,rate
+//! EUR,USD,1.0850
+//! USD,JPY,151.20
+//! ".as_bytes()).unwrap();
+//!
+//! let eur = "EUR".parse().unwrap();
+//! let jpy = "JPY".parse().unwrap();
+//! let price = Decimal::new(1999, 2);
+//! // Direct: 19.99 * 1.0850 = 21.689150
+//! assert_eq!(Ok(Decimal::new(2169, 2)), rates.convert(price, eur, usd, RoundingMode::HalfEven));
+//! // Inverse: 19.99 / 1.0850 = 18.42396...
+//! assert_eq!(Ok(Decimal::new(1842, 2)), rates.convert(price, usd, eur, RoundingMode::HalfEven));
+//! // Triangulated through USD, to yen, which have no minor units: 19.99 * 1.0850 * 151.20
+//! assert_eq!(Ok(Decimal::new(3279, 0)), rates.convert(price, eur, jpy, RoundingMode::HalfEven));
+//! ```
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::io::{self, Read};
+use std::str;
+
+use {divide_to_scale, ArithmeticError, Decimal, ParseDecimalError, RoundingMode};
+
+/// An ISO 4217 currency code, such as `USD`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Currency {
+    code: [u8; 3],
+}
+
+impl Currency {
+    /// The currency with the given code, which must be three ASCII capital letters.
+    pub fn new(code: &str) -> Result<Currency, ExchangeError> {
+        match code.as_bytes() {
+            &[a, b, c] if code.bytes().all(|b| b.is_ascii_uppercase()) =>
+                Ok(Currency { code: [a, b, c] }),
+            _ => Err(ExchangeError::InvalidCurrency(code.to_string())),
+        }
+    }
+
+    pub fn code(&self) -> &str {
+        str::from_utf8(&self.code).expect("currency codes are ASCII")
+    }
+
+    /// The number of decimal places in the currency's minor unit, per ISO 4217: 2 for
+    /// most currencies, 0 for the likes of `JPY` and `KRW`, 3 for the likes of `KWD`.
+    pub fn minor_units(&self) -> i32 {
+        match self.code() {
+            "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" |
+            "UGX" | "UYI" | "VND" | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+            "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+            "CLF" | "UYW" => 4,
+            _ => 2,
+        }
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl fmt::Debug for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Currency({})", self.code())
+    }
+}
+
+impl str::FromStr for Currency {
+    type Err = ExchangeError;
+    fn from_str(s: &str) -> Result<Currency, ExchangeError> {
+        Currency::new(s)
+    }
+}
+
+/// Exchange rates between currencies, with a base currency to triangulate through.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateTable {
+    base: Currency,
+    rates: HashMap<(Currency, Currency), Decimal>,
+}
+
+/// A conversion as a multiplier and divisor, so it can be applied with one rounding.
+/// Each is an unscaled value and scale, kept in an `i128` so that chaining two rates and
+/// multiplying an amount by them doesn't overflow where the result itself would fit.
+#[derive(Debug, Clone, Copy)]
+struct Conversion {
+    multiply: (i128, i64),
+    divide: (i128, i64),
+}
+
+impl Conversion {
+    fn new(multiply: Decimal, divide: Decimal) -> Conversion {
+        Conversion {
+            multiply: (multiply.unscaled as i128, multiply.scale as i64),
+            divide: (divide.unscaled as i128, divide.scale as i64),
+        }
+    }
+
+    fn then(self, next: Conversion) -> Result<Conversion, ArithmeticError> {
+        let mul = |(a, a_scale): (i128, i64), (b, b_scale): (i128, i64)| {
+            a.checked_mul(b).map(|n| (n, a_scale + b_scale)).ok_or(ArithmeticError::Overflow)
+        };
+        Ok(Conversion {
+            multiply: mul(self.multiply, next.multiply)?,
+            divide: mul(self.divide, next.divide)?,
+        })
+    }
+
+    fn apply(&self, amount: Decimal, scale: i32, mode: RoundingMode)
+             -> Result<Decimal, ArithmeticError> {
+        let (multiply, multiply_scale) = self.multiply;
+        let (divide, divide_scale) = self.divide;
+        let numer = (amount.unscaled as i128).checked_mul(multiply)
+            .ok_or(ArithmeticError::Overflow)?;
+        divide_to_scale(numer, amount.scale as i64 + multiply_scale, divide, divide_scale,
+                        scale, mode)
+    }
+}
+
+impl RateTable {
+    /// An empty table that triangulates through `base`.
+    pub fn new(base: Currency) -> RateTable {
+        RateTable { base, rates: HashMap::new() }
+    }
+
+    pub fn base(&self) -> Currency {
+        self.base
+    }
+
+    /// Sets the rate from `from` to `to`: one unit of `from` buys `rate` units of `to`.
+    /// The rate must be positive.
+    pub fn set_rate(&mut self, from: Currency, to: Currency, rate: Decimal)
+                    -> Result<(), ExchangeError> {
+        if rate.unscaled <= 0 {
+            return Err(ExchangeError::InvalidRate(rate));
+        }
+        self.rates.insert((from, to), rate);
+        Ok(())
+    }
+
+    /// The rate set from `from` to `to`, if any. Unlike conversions, this doesn't look
+    /// for inverse or triangulated rates.
+    pub fn get_rate(&self, from: Currency, to: Currency) -> Option<Decimal> {
+        self.rates.get(&(from, to)).cloned()
+    }
+
+    /// Converts `amount` of `from` into `to`, rounded to `to`'s minor units according to
+    /// `mode`.
+    pub fn convert(&self, amount: Decimal, from: Currency, to: Currency, mode: RoundingMode)
+                   -> Result<Decimal, ExchangeError> {
+        self.convert_to_scale(amount, from, to, to.minor_units(), mode)
+    }
+
+    /// Converts `amount` of `from` into `to`, rounded to `scale` according to `mode`.
+    pub fn convert_to_scale(&self, amount: Decimal, from: Currency, to: Currency, scale: i32,
+                            mode: RoundingMode) -> Result<Decimal, ExchangeError> {
+        Ok(self.conversion(from, to)?.apply(amount, scale, mode)?)
+    }
+
+    /// The effective rate from `from` to `to`, however it's found, rounded to `scale`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{Decimal, RoundingMode};
+    /// # use decimal::exchange::{Currency, RateTable};
+    /// let (usd, gbp) = ("USD".parse().unwrap(), "GBP".parse().unwrap());
+    /// let mut rates = RateTable::new(usd);
+    /// rates.set_rate(gbp, usd, Decimal::new(127, 2)).unwrap();
+    /// assert_eq!(Ok(Decimal::new(7874, 4)), rates.rate(usd, gbp, 4, RoundingMode::HalfEven));
+    /// ```
+    pub fn rate(&self, from: Currency, to: Currency, scale: i32, mode: RoundingMode)
+                -> Result<Decimal, ExchangeError> {
+        self.convert_to_scale(Decimal::new(1, 0), from, to, scale, mode)
+    }
+
+    fn conversion(&self, from: Currency, to: Currency) -> Result<Conversion, ExchangeError> {
+        if let Some(conversion) = self.leg(from, to) {
+            return Ok(conversion);
+        }
+        match (self.leg(from, self.base), self.leg(self.base, to)) {
+            (Some(first), Some(second)) => Ok(first.then(second)?),
+            _ => Err(ExchangeError::NoRate { from, to }),
+        }
+    }
+
+    /// A conversion using a direct or inverse rate, without triangulating.
+    fn leg(&self, from: Currency, to: Currency) -> Option<Conversion> {
+        let one = Decimal::new(1, 0);
+        if from == to {
+            Some(Conversion::new(one, one))
+        } else if let Some(rate) = self.get_rate(from, to) {
+            Some(Conversion::new(rate, one))
+        } else {
+            self.get_rate(to, from).map(|rate| Conversion::new(one, rate))
+        }
+    }
+
+    /// Reads rates from CSV with `from,to,rate` rows, such as `EUR,USD,1.0850`.
+    ///
+    /// A first row of `from,to,rate` is taken as a header. Blank lines and lines starting
+    /// with `#` are skipped, and space around fields is ignored. Fields can't be quoted,
+    /// which rates and currency codes never need.
+    pub fn from_csv<R: Read>(base: Currency, mut input: R) -> Result<RateTable, LoadRatesError> {
+        let mut text = String::new();
+        input.read_to_string(&mut text).map_err(LoadRatesError::Io)?;
+        let mut table = RateTable::new(base);
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if fields == [""] || line.trim_start().starts_with('#')
+                || (i == 0 && fields == ["from", "to", "rate"]) {
+                continue;
+            }
+            let error = |kind| LoadRatesError::Line { line: line_number, kind };
+            if fields.len() != 3 {
+                return Err(error(LineErrorKind::FieldCount(fields.len())));
+            }
+            let from = Currency::new(fields[0])
+                .map_err(|_| error(LineErrorKind::Currency(fields[0].to_string())))?;
+            let to = Currency::new(fields[1])
+                .map_err(|_| error(LineErrorKind::Currency(fields[1].to_string())))?;
+            let rate = fields[2].parse().map_err(|e| error(LineErrorKind::Rate(e)))?;
+            table.set_rate(from, to, rate).map_err(|_| error(LineErrorKind::NotPositive(rate)))?;
+        }
+        Ok(table)
+    }
+}
+
+/// The error returned when a currency code is malformed or a conversion fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExchangeError {
+    /// A currency code that isn't three ASCII capital letters.
+    InvalidCurrency(String),
+    /// A rate that is zero or negative.
+    InvalidRate(Decimal),
+    /// No direct, inverse or triangulated rate between the currencies.
+    NoRate { from: Currency, to: Currency },
+    /// The converted amount, or a product of rates, doesn't fit in a `Decimal`.
+    Overflow,
+}
+
+impl fmt::Display for ExchangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExchangeError::InvalidCurrency(ref code) => write!(f, "invalid currency code '{}'", code),
+            ExchangeError::InvalidRate(rate) => write!(f, "exchange rate {} is not positive", rate),
+            ExchangeError::NoRate { from, to } => write!(f, "no exchange rate from {} to {}", from, to),
+            ExchangeError::Overflow => f.write_str("currency conversion overflowed"),
+        }
+    }
+}
+
+impl error::Error for ExchangeError {}
+
+impl From<ArithmeticError> for ExchangeError {
+    fn from(_: ArithmeticError) -> ExchangeError {
+        ExchangeError::Overflow
+    }
+}
+
+/// The error returned when reading a rate table fails.
+#[derive(Debug)]
+pub enum LoadRatesError {
+    Io(io::Error),
+    /// A line (counting from 1) that isn't a valid rate.
+    Line { line: usize, kind: LineErrorKind },
+}
+
+/// What's wrong with a line of a rate table.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineErrorKind {
+    /// A line without exactly three fields; this many instead.
+    FieldCount(usize),
+    Currency(String),
+    Rate(ParseDecimalError),
+    NotPositive(Decimal),
+}
+
+impl fmt::Display for LoadRatesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadRatesError::Io(ref e) => write!(f, "failed to read exchange rates: {}", e),
+            LoadRatesError::Line { line, ref kind } => {
+                write!(f, "line {}: ", line)?;
+                match *kind {
+                    LineErrorKind::FieldCount(n) =>
+                        write!(f, "expected 3 fields (from,to,rate), found {}", n),
+                    LineErrorKind::Currency(ref code) =>
+                        write!(f, "invalid currency code '{}'", code),
+                    LineErrorKind::Rate(ref e) => write!(f, "invalid rate: {}", e),
+                    LineErrorKind::NotPositive(rate) =>
+                        write!(f, "exchange rate {} is not positive", rate),
+                }
+            }
+        }
+    }
+}
+
+impl error::Error for LoadRatesError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            LoadRatesError::Io(ref e) => Some(e),
+            LoadRatesError::Line { kind: LineErrorKind::Rate(ref e), .. } => Some(e),
+            LoadRatesError::Line { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Currency, ExchangeError, LineErrorKind, LoadRatesError, RateTable};
+    use {Decimal, RoundingMode};
+    use RoundingMode::*;
+
+    fn c(code: &str) -> Currency {
+        code.parse().unwrap()
+    }
+
+    fn d(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    fn table() -> RateTable {
+        let mut rates = RateTable::new(c("USD"));
+        rates.set_rate(c("EUR"), c("USD"), d("1.0850")).unwrap();
+        rates.set_rate(c("USD"), c("JPY"), d("151.20")).unwrap();
+        rates.set_rate(c("USD"), c("KWD"), d("0.3075")).unwrap();
+        rates.set_rate(c("GBP"), c("EUR"), d("1.17")).unwrap();
+        rates
+    }
+
+    fn convert(amount: &str, from: &str, to: &str, mode: RoundingMode)
+               -> Result<Decimal, ExchangeError> {
+        table().convert(d(amount), c(from), c(to), mode)
+    }
+
+    #[test]
+    fn currency_codes() {
+        assert_eq!("EUR", c("EUR").to_string());
+        assert_eq!((2, 0, 3), (c("EUR").minor_units(), c("JPY").minor_units(), c("KWD").minor_units()));
+        for bad in &["usd", "US", "USDX", "", "U$D", "ÜSD"] {
+            assert_eq!(Err(ExchangeError::InvalidCurrency(bad.to_string())), Currency::new(bad));
+        }
+    }
+
+    #[test]
+    fn converts_directly_and_inversely() {
+        assert_eq!(Ok(d("108.50")), convert("100", "EUR", "USD", HalfEven));
+        assert_eq!(Ok(d("92.17")), convert("100", "USD", "EUR", HalfEven));
+        assert_eq!(Ok(d("92.16")), convert("100", "USD", "EUR", Down));
+        assert_eq!(Ok(d("15120")), convert("100", "USD", "JPY", HalfEven));
+        assert_eq!(Ok(d("0.07")), convert("10", "JPY", "USD", Up));
+        assert_eq!(Ok(d("30.750")), convert("100", "USD", "KWD", HalfEven));
+        assert_eq!(Ok(d("12.34")), convert("12.34", "EUR", "EUR", HalfEven));
+        assert_eq!(Ok(d("12.35")), convert("12.345", "EUR", "EUR", HalfUp));
+    }
+
+    #[test]
+    fn triangulates_through_the_base() {
+        // 100 EUR -> 108.50 USD -> 16405.2 JPY, rounded once.
+        assert_eq!(Ok(d("16405")), convert("100", "EUR", "JPY", HalfEven));
+        // 10000 JPY -> 66.137... USD -> 20.337... KWD
+        assert_eq!(Ok(d("20.337")), convert("10000", "JPY", "KWD", HalfEven));
+        // GBP only has a rate to EUR, which isn't the base.
+        assert_eq!(Err(ExchangeError::NoRate { from: c("GBP"), to: c("JPY") }),
+                   convert("1", "GBP", "JPY", HalfEven));
+        assert_eq!(Ok(d("117.00")), convert("100", "GBP", "EUR", HalfEven));
+        assert_eq!(Err(ExchangeError::NoRate { from: c("USD"), to: c("CHF") }),
+                   convert("1", "USD", "CHF", HalfEven));
+    }
+
+    #[test]
+    fn converts_large_amounts() {
+        assert_eq!(Ok(d("10850000000000.00")),
+                   convert("10000000000000.00", "EUR", "USD", HalfEven));
+        assert_eq!(Ok(d("164052000000")), convert("1000000000.00", "EUR", "JPY", HalfEven));
+        assert_eq!(Ok(d("85008037206034800.06")),
+                   convert("92233720368547758.07", "USD", "EUR", HalfEven));
+        assert_eq!(Ok(d("7560000000000000000")),
+                   convert("50000000000000000.00", "USD", "JPY", HalfEven));
+    }
+
+    #[test]
+    fn effective_rates() {
+        let rates = table();
+        assert_eq!(Ok(d("0.9217")), rates.rate(c("USD"), c("EUR"), 4, HalfEven));
+        assert_eq!(Ok(d("164.052000")), rates.rate(c("EUR"), c("JPY"), 6, HalfEven));
+        assert_eq!(Some(d("1.0850")), rates.get_rate(c("EUR"), c("USD")));
+        assert_eq!(None, rates.get_rate(c("USD"), c("EUR")));
+    }
+
+    #[test]
+    fn rejects_nonpositive_rates_and_reports_overflow() {
+        let mut rates = table();
+        assert_eq!(Err(ExchangeError::InvalidRate(d("0"))),
+                   rates.set_rate(c("USD"), c("CHF"), d("0")));
+        assert_eq!(Err(ExchangeError::InvalidRate(d("-1.2"))),
+                   rates.set_rate(c("USD"), c("CHF"), d("-1.2")));
+        assert_eq!(Err(ExchangeError::Overflow),
+                   convert("9223372036854775807", "USD", "JPY", HalfEven));
+    }
+
+    #[test]
+    fn loads_csv() {
+        let csv = "from,to,rate\n\n# majors\nEUR, USD, 1.0850\nUSD,JPY,151.20\n";
+        let rates = RateTable::from_csv(c("USD"), csv.as_bytes()).unwrap();
+        assert_eq!(c("USD"), rates.base());
+        assert_eq!(Some(d("1.0850")), rates.get_rate(c("EUR"), c("USD")));
+        assert_eq!(Some(d("151.20")), rates.get_rate(c("USD"), c("JPY")));
+        assert_eq!(RateTable::new(c("EUR")), RateTable::from_csv(c("EUR"), "".as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn reports_bad_csv_lines() {
+        let error = |csv: &str| match RateTable::from_csv(c("USD"), csv.as_bytes()) {
+            Err(LoadRatesError::Line { line, kind }) => (line, kind),
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!((2, LineErrorKind::FieldCount(2)), error("EUR,USD,1\nEUR,USD\n"));
+        assert_eq!((1, LineErrorKind::Currency("eur".to_string())), error("eur,USD,1"));
+        assert_eq!((1, LineErrorKind::Currency("".to_string())), error("EUR,,1"));
+        assert_eq!((1, LineErrorKind::NotPositive(d("0.00"))), error("EUR,USD,0.00"));
+        assert_eq!((2, LineErrorKind::Currency("from".to_string())),
+                   error("from,to,rate\nfrom,to,rate\n"));
+        match error("EUR,USD,1.2x") {
+            (1, LineErrorKind::Rate(e)) => assert_eq!(Some(3..4), e.span()),
+            other => panic!("unexpected {:?}", other),
+        }
+        let message = RateTable::from_csv(c("USD"), "EUR,USD,1.2x".as_bytes()).unwrap_err().to_string();
+        assert_eq!("line 1: invalid rate: invalid character 'x' found at index 3", message);
+    }
+}
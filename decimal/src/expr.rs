@@ -0,0 +1,512 @@
+//! Arithmetic formulas over `Decimal`s, such as `base * (1 + tax_rate) - discount`.
+//!
+//! A formula is parsed once into an `Expr` and can then be evaluated any number of
+//! times with different values bound to its variables. Formulas use `+ - * / %`,
+//! unary minus and parentheses with the usual precedence; variable names are ASCII
+//! letters, digits and underscores, not starting with a digit.
+//!
+//! Evaluation uses the checked operations (`try_add` and friends), so overflow and
+//! division by zero come back as an `EvalError` rather than a panic. The `Context`
+//! decides how division rounds.
+//!
+//! # Examples
+//! ```
+//! # use std::collections::HashMap;
+//! # use decimal::{Decimal, RoundingMode};
+//! # use decimal::expr::{Context, EvalError, Expr};
+//! let price: Expr = "base * (1 + tax_rate) - discount".parse().unwrap();
+//! let mut variables = HashMap::new();
+//! variables.insert("base".to_string(), Decimal::new(2000, 2));
+//! variables.insert("tax_rate".to_string(), Decimal::new(825, 4));
+//! variables.insert("discount".to_string(), Decimal::new(150, 2));
+//! let context = Context::new(2, RoundingMode::HalfUp);
+//! assert_eq!(Ok(Decimal::new(2015, 2)), price.evaluate(&variables, context));
+//!
+//! variables.remove("discount");
+//! assert_eq!(Err(EvalError::UnknownVariable("discount".to_string())),
+//!            price.evaluate(&variables, context));
+//! ```
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::str;
+
+use {ArithmeticError, Decimal, DecimalErrorKind, ParseDecimalError, RoundingMode};
+
+/// A parsed formula.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(Decimal),
+    Variable(String),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+/// The operators that combine two `Expr`s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    /// Division, rounded as the `Context` says.
+    Div,
+    /// Remainder, as `Decimal::try_rem`.
+    Rem,
+}
+
+/// How to evaluate an `Expr`.
+///
+/// With no scale, `/` truncates like `Decimal`'s `/` operator. With one, each division
+/// is rounded to that scale using `rounding`, and so is the final result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Context {
+    pub scale: Option<i32>,
+    pub rounding: RoundingMode,
+}
+
+impl Context {
+    /// A context that rounds to `scale`.
+    pub fn new(scale: i32, rounding: RoundingMode) -> Context {
+        Context { scale: Some(scale), rounding }
+    }
+}
+
+/// No scale, and `HalfEven` rounding should one be set.
+impl Default for Context {
+    fn default() -> Context {
+        Context { scale: None, rounding: RoundingMode::HalfEven }
+    }
+}
+
+impl Expr {
+    /// The value of this formula with `variables` bound to their values.
+    pub fn evaluate(&self, variables: &HashMap<String, Decimal>, context: Context)
+                    -> Result<Decimal, EvalError> {
+        let value = self.eval(variables, context)?;
+        match context.scale {
+            Some(scale) => Ok(value.div_round(Decimal::new(1, 0), scale, context.rounding)?),
+            None => Ok(value),
+        }
+    }
+
+    fn eval(&self, variables: &HashMap<String, Decimal>, context: Context)
+            -> Result<Decimal, EvalError> {
+        match *self {
+            Expr::Number(n) => Ok(n),
+            Expr::Variable(ref name) =>
+                variables.get(name).cloned().ok_or_else(|| EvalError::UnknownVariable(name.clone())),
+            Expr::Neg(ref e) => {
+                let value = e.eval(variables, context)?;
+                Ok(Decimal::new(0, value.scale).try_sub(value)?)
+            }
+            Expr::Binary(op, ref lhs, ref rhs) => {
+                let a = lhs.eval(variables, context)?;
+                let b = rhs.eval(variables, context)?;
+                Ok(match (op, context.scale) {
+                    (BinaryOp::Add, _) => a.try_add(b),
+                    (BinaryOp::Sub, _) => a.try_sub(b),
+                    (BinaryOp::Mul, _) => a.try_mul(b),
+                    (BinaryOp::Div, Some(scale)) => a.div_round(b, scale, context.rounding),
+                    (BinaryOp::Div, None) => a.try_div(b),
+                    (BinaryOp::Rem, _) => a.try_rem(b),
+                }?)
+            }
+        }
+    }
+}
+
+/// Parses a formula.
+///
+/// # Examples
+/// ```
+/// # use decimal::Decimal;
+/// # use decimal::expr::{BinaryOp, Expr};
+/// let parsed: Expr = "-x * 2".parse().unwrap();
+/// let x = Expr::Neg(Box::new(Expr::Variable("x".to_string())));
+/// let two = Expr::Number(Decimal::new(2, 0));
+/// assert_eq!(Expr::Binary(BinaryOp::Mul, Box::new(x), Box::new(two)), parsed);
+///
+/// let err = "1 + 2 )".parse::<Expr>().unwrap_err();
+/// assert_eq!("1 + 2 )\n      ^ unmatched ')'", err.annotate("1 + 2 )"));
+/// ```
+impl str::FromStr for Expr {
+    type Err = ParseExprError;
+
+    fn from_str(s: &str) -> Result<Expr, ParseExprError> {
+        let mut parser = Parser { tokens: tokenize(s)?, pos: 0, end: s.chars().count() };
+        let expr = parser.expr()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(&Token::Close) => Err(parser.error(ParseExprErrorKind::UnmatchedParen)),
+            Some(_) => Err(parser.error(ParseExprErrorKind::ExpectedOperator)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(Decimal),
+    Name(String),
+    Op(BinaryOp),
+    Open,
+    Close,
+}
+
+/// Splits `s` into tokens, each paired with the character column it starts at.
+fn tokenize(s: &str) -> Result<Vec<(usize, Token)>, ParseExprError> {
+    let chars: Vec<(usize, char)> = s.char_indices().collect();
+    let byte_at = |i: usize| chars.get(i).map_or(s.len(), |&(b, _)| b);
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i].1;
+        i += 1;
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' => Token::Op(BinaryOp::Add),
+            '-' => Token::Op(BinaryOp::Sub),
+            '*' => Token::Op(BinaryOp::Mul),
+            '/' => Token::Op(BinaryOp::Div),
+            '%' => Token::Op(BinaryOp::Rem),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '0'..='9' | '.' => {
+                // Take everything that could be part of a number and let FromStr judge it,
+                // so a malformed number gets its error rather than a confusing one here.
+                while i < chars.len() {
+                    let c = chars[i].1;
+                    let after_e = chars[i - 1].1 == 'e' || chars[i - 1].1 == 'E';
+                    if !(c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E'
+                         || (after_e && (c == '+' || c == '-'))) {
+                        break;
+                    }
+                    i += 1;
+                }
+                let number = s[byte_at(start)..byte_at(i)].parse()
+                    .map_err(|e| ParseExprError::number(start, e))?;
+                Token::Number(number)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].1.is_ascii_alphanumeric() || chars[i].1 == '_') {
+                    i += 1;
+                }
+                Token::Name(s[byte_at(start)..byte_at(i)].to_string())
+            }
+            c => return Err(ParseExprError { kind: ParseExprErrorKind::UnexpectedChar(c),
+                                             column: start }),
+        };
+        tokens.push((start, token));
+    }
+    Ok(tokens)
+}
+
+/// A recursive-descent parser over the grammar
+///
+/// ```text
+/// expr  = term (("+" | "-") term)*
+/// term  = unary (("*" | "/" | "%") unary)*
+/// unary = ("-" | "+") unary | number | name | "(" expr ")"
+/// ```
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    /// The column just past the input, for errors at the end.
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn error(&self, kind: ParseExprErrorKind) -> ParseExprError {
+        let column = self.tokens.get(self.pos).map_or(self.end, |&(column, _)| column);
+        ParseExprError { kind, column }
+    }
+
+    /// Consumes the next token if it is one of `ops`.
+    fn next_op(&mut self, ops: &[BinaryOp]) -> Option<BinaryOp> {
+        match self.peek() {
+            Some(&Token::Op(op)) if ops.contains(&op) => {
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseExprError> {
+        let mut expr = self.term()?;
+        while let Some(op) = self.next_op(&[BinaryOp::Add, BinaryOp::Sub]) {
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.term()?));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr, ParseExprError> {
+        let mut expr = self.unary()?;
+        while let Some(op) = self.next_op(&[BinaryOp::Mul, BinaryOp::Div, BinaryOp::Rem]) {
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseExprError> {
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return Err(self.error(ParseExprErrorKind::UnexpectedEnd)),
+        };
+        match token {
+            Token::Op(BinaryOp::Sub) => {
+                self.pos += 1;
+                Ok(Expr::Neg(Box::new(self.unary()?)))
+            }
+            Token::Op(BinaryOp::Add) => {
+                self.pos += 1;
+                self.unary()
+            }
+            Token::Number(n) => {
+                self.pos += 1;
+                Ok(Expr::Number(n))
+            }
+            Token::Name(name) => {
+                self.pos += 1;
+                Ok(Expr::Variable(name))
+            }
+            Token::Open => {
+                self.pos += 1;
+                let expr = self.expr()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err(self.error(ParseExprErrorKind::UnclosedParen));
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            Token::Op(_) | Token::Close => Err(self.error(ParseExprErrorKind::ExpectedOperand)),
+        }
+    }
+}
+
+/// The error returned when parsing an `Expr` fails.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseExprError {
+    kind: ParseExprErrorKind,
+    column: usize,
+}
+
+impl ParseExprError {
+    fn number(start: usize, e: ParseDecimalError) -> ParseExprError {
+        let offset = match *e.kind() {
+            DecimalErrorKind::InvalidChar { char_index, .. } => char_index,
+            _ => 0,
+        };
+        ParseExprError { kind: ParseExprErrorKind::InvalidNumber(e), column: start + offset }
+    }
+
+    /// What went wrong.
+    pub fn kind(&self) -> &ParseExprErrorKind {
+        &self.kind
+    }
+
+    /// The character offset in the input where the problem was found, which is one past
+    /// the end if the input ended too soon.
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Renders `input` (the string that failed to parse) with a caret under the error's
+    /// column and the message beside it, like `ParseDecimalError::annotate`.
+    pub fn annotate(&self, input: &str) -> String {
+        format!("{}\n{}^ {}", input, " ".repeat(self.column), self)
+    }
+}
+
+impl fmt::Display for ParseExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            // The number's own message counts from the start of the number, so give the
+            // position in the whole expression instead.
+            ParseExprErrorKind::InvalidNumber(ref e) => match *e.kind() {
+                DecimalErrorKind::InvalidChar { character, .. } => write!(
+                    f, "invalid character '{}' in number at index {}", character, self.column),
+                _ => e.fmt(f),
+            },
+            ParseExprErrorKind::UnexpectedChar(c) => write!(f, "unexpected character '{}'", c),
+            ParseExprErrorKind::UnexpectedEnd => f.write_str("unexpected end of expression"),
+            ParseExprErrorKind::ExpectedOperand => f.write_str("expected a number, name or '('"),
+            ParseExprErrorKind::ExpectedOperator => f.write_str("expected an operator"),
+            ParseExprErrorKind::UnclosedParen => f.write_str("expected ')'"),
+            ParseExprErrorKind::UnmatchedParen => f.write_str("unmatched ')'"),
+        }
+    }
+}
+
+impl error::Error for ParseExprError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self.kind {
+            ParseExprErrorKind::InvalidNumber(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The reasons parsing an `Expr` can fail. More may be added, so matches need a
+/// wildcard arm.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ParseExprErrorKind {
+    /// A number that `Decimal` couldn't parse.
+    InvalidNumber(ParseDecimalError),
+    /// A character that can't start any token.
+    UnexpectedChar(char),
+    /// The input ended where an operand was needed, as in `1 +`.
+    UnexpectedEnd,
+    /// An operator or `)` where an operand was needed, as in `* 2`.
+    ExpectedOperand,
+    /// An operand where an operator was needed, as in `1 2`.
+    ExpectedOperator,
+    /// A `(` without its `)`.
+    UnclosedParen,
+    /// A `)` without its `(`.
+    UnmatchedParen,
+}
+
+/// The error returned when evaluating an `Expr` fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    /// A variable with no value bound to it.
+    UnknownVariable(String),
+    /// A division or remainder by zero.
+    DivisionByZero,
+    /// A result or intermediate value too large for a `Decimal`.
+    Overflow,
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EvalError::UnknownVariable(ref name) => write!(f, "unknown variable '{}'", name),
+            EvalError::DivisionByZero => f.write_str("decimal division by zero"),
+            EvalError::Overflow => f.write_str("expression overflowed"),
+        }
+    }
+}
+
+impl error::Error for EvalError {}
+
+impl From<ArithmeticError> for EvalError {
+    fn from(e: ArithmeticError) -> EvalError {
+        match e {
+            ArithmeticError::DivisionByZero => EvalError::DivisionByZero,
+            _ => EvalError::Overflow,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{BinaryOp, Context, EvalError, Expr, ParseExprErrorKind};
+    use {Decimal, RoundingMode};
+
+    fn eval(s: &str) -> Result<Decimal, EvalError> {
+        s.parse::<Expr>().unwrap().evaluate(&HashMap::new(), Context::default())
+    }
+
+    fn eval_at(s: &str, scale: i32, rounding: RoundingMode) -> Result<Decimal, EvalError> {
+        s.parse::<Expr>().unwrap().evaluate(&HashMap::new(), Context::new(scale, rounding))
+    }
+
+    fn parse_error(s: &str) -> (ParseExprErrorKind, usize) {
+        let e = s.parse::<Expr>().unwrap_err();
+        (e.kind().clone(), e.column())
+    }
+
+    #[test]
+    fn parses_with_precedence_and_associativity() {
+        use super::BinaryOp::*;
+        fn n(i: i64) -> Box<Expr> { Box::new(Expr::Number(Decimal::new(i, 0))) }
+        fn bin(op: BinaryOp, a: Box<Expr>, b: Box<Expr>) -> Box<Expr> {
+            Box::new(Expr::Binary(op, a, b))
+        }
+        assert_eq!(*bin(Add, n(1), bin(Mul, n(2), n(3))), "1 + 2 * 3".parse().unwrap());
+        assert_eq!(*bin(Mul, bin(Add, n(1), n(2)), n(3)), "(1 + 2) * 3".parse().unwrap());
+        assert_eq!(*bin(Sub, bin(Sub, n(1), n(2)), n(3)), "1 - 2 - 3".parse().unwrap());
+        assert_eq!(*bin(Rem, bin(Div, n(1), n(2)), n(3)), "1/2%3".parse().unwrap());
+        assert_eq!(Expr::Neg(Box::new(Expr::Neg(Box::new(Expr::Variable("_a1".to_string()))))),
+                   "--_a1".parse().unwrap());
+        assert_eq!(Expr::Number(Decimal::new(25, -1)), "+2.5e+2".parse().unwrap());
+    }
+
+    #[test]
+    fn evaluates() {
+        assert_eq!(Ok(Decimal::new(28, 0)), eval("3.50 * 2 / 0.25"));
+        assert_eq!(Ok(Decimal::new(-15, 1)), eval("1 - 2 + -(-.5) - (1)"));
+        assert_eq!(Ok(Decimal::new(15, 1)), eval("7.5 % 2.0"));
+        assert_eq!(Ok(Decimal::new(25, 0)), eval("2.5e+2 - 2.5E1*9"));
+        assert_eq!(Ok(Decimal::new(0, 0)), eval("1 / 3"));
+    }
+
+    #[test]
+    fn context_rounds_division_and_the_result() {
+        use RoundingMode::*;
+        assert_eq!(Ok(Decimal::new(3333, 4)), eval_at("1 / 3", 4, HalfEven));
+        assert_eq!(Ok(Decimal::new(6667, 4)), eval_at("2 / 3", 4, HalfEven));
+        assert_eq!(Ok(Decimal::new(6666, 4)), eval_at("2 / 3", 4, Down));
+        assert_eq!(Ok(Decimal::new(12, 1)), eval_at("1.25", 1, HalfEven));
+        assert_eq!(Ok(Decimal::new(13, 1)), eval_at("1.25", 1, HalfUp));
+        assert_eq!(Ok(Decimal::new(200, 2)), eval_at("1 * 2", 2, HalfEven));
+    }
+
+    #[test]
+    fn binds_variables() {
+        let formula: Expr = "qty * unit_price - qty * unit_price * discount".parse().unwrap();
+        let mut variables = HashMap::new();
+        variables.insert("qty".to_string(), Decimal::new(3, 0));
+        variables.insert("unit_price".to_string(), Decimal::new(1999, 2));
+        assert_eq!(Err(EvalError::UnknownVariable("discount".to_string())),
+                   formula.evaluate(&variables, Context::default()));
+        variables.insert("discount".to_string(), Decimal::new(15, 2));
+        assert_eq!(Ok(Decimal::new(5097, 2)),
+                   formula.evaluate(&variables, Context::new(2, RoundingMode::HalfUp)));
+        variables.insert("qty".to_string(), Decimal::new(0, 0));
+        assert_eq!(Ok(Decimal::new(0, 4)), formula.evaluate(&variables, Context::default()));
+    }
+
+    #[test]
+    fn evaluation_errors() {
+        assert_eq!(Err(EvalError::DivisionByZero), eval("1.5 / (2 - 2)"));
+        assert_eq!(Err(EvalError::DivisionByZero), eval("1 % 0"));
+        assert_eq!(Err(EvalError::DivisionByZero), eval_at("1 / 0", 2, RoundingMode::Up));
+        assert_eq!(Err(EvalError::Overflow), eval("9223372036854775807 + 1"));
+        assert_eq!(Err(EvalError::Overflow), eval("-(-9223372036854775807 - 1)"));
+        assert_eq!(Err(EvalError::Overflow), eval_at("9223372036854775807", 1, RoundingMode::Up));
+    }
+
+    #[test]
+    fn parse_errors_point_at_their_cause() {
+        use super::ParseExprErrorKind::*;
+        match parse_error("1 + 2.3.4") {
+            (InvalidNumber(ref e), 7) => assert_eq!("invalid character '.' found at index 3",
+                                                    e.to_string()),
+            other => panic!("unexpected {:?}", other),
+        }
+        let err = "1 + 2.3.4".parse::<Expr>().unwrap_err();
+        assert_eq!("1 + 2.3.4\n       ^ invalid character '.' in number at index 7",
+                   err.annotate("1 + 2.3.4"));
+        let err = "1 + 99999999999999999999".parse::<Expr>().unwrap_err();
+        assert_eq!("number too large for decimal", err.to_string());
+        assert_eq!((UnexpectedChar('&'), 2), parse_error("1 & 2"));
+        assert_eq!((UnexpectedChar('é'), 4), parse_error("1 + é"));
+        assert_eq!((UnexpectedEnd, 3), parse_error("1 +"));
+        assert_eq!((UnexpectedEnd, 0), parse_error(""));
+        assert_eq!((UnclosedParen, 6), parse_error("(1 + 2"));
+        assert_eq!((UnmatchedParen, 1), parse_error("1)"));
+        assert_eq!((ExpectedOperator, 2), parse_error("1 x"));
+        assert_eq!((ExpectedOperand, 0), parse_error("* 2"));
+        assert_eq!((ExpectedOperand, 1), parse_error("()"));
+    }
+}
@@ -0,0 +1,777 @@
+//! Double-entry bookkeeping.
+//!
+//! A `Ledger` holds accounts and the journal entries posted to them. Each posting's
+//! amount is a debit if positive and a credit if negative, and an entry is only posted
+//! if its postings sum to exactly zero, so the ledger as a whole always balances.
+//!
+//! A `Journal` keeps a ledger in an append-only file, one record per line: `open`
+//! records open accounts, and `entry` records post entries, with the fields separated
+//! by tabs (shown here as `→`) and any tab, newline, carriage return or backslash in
+//! them escaped with a backslash:
+//!
+//! ```text
+//! open→assets:cash
+//! open→income:sales
+//! entry→Cash sale→assets:cash→19.99→income:sales→-19.99
+//! ```
+//!
+//! Reading a journal replays its records, so a file that's been edited into something
+//! unbalanced is rejected. Blank lines and lines starting with `#` are skipped.
+//!
+//! # Examples
+//! ```
+//! # use decimal::Decimal;
+//! # use decimal::ledger::{Entry, Ledger, Posting};
+//! let mut ledger = Ledger::new();
+//! for account in &["assets:cash", "income:sales", "expenses:rent"] {
+//!     ledger.open_account(account).unwrap();
+//! }
+//! ledger.post(Entry::new("Cash sale", vec![Posting::debit("assets:cash", Decimal::new(1999, 2)),
+//!                                          Posting::credit("income:sales", Decimal::new(1999, 2))]))
+//!     .unwrap();
+//! ledger.post(Entry::new("Rent", vec![Posting::debit("expenses:rent", Decimal::new(12, 0)),
+//!                                     Posting::credit("assets:cash", Decimal::new(12, 0))]))
+//!     .unwrap();
+//! assert_eq!(Some(Decimal::new(799, 2)), ledger.balance("assets:cash"));
+//!
+//! // Postings that don't balance are rejected.
+//! assert!(ledger.post(Entry::new("Typo", vec![Posting::debit("assets:cash", Decimal::new(10, 0)),
+//!                                             Posting::credit("income:sales", Decimal::new(1, 0))]))
+//!               .is_err());
+//!
+//! let trial_balance = ledger.trial_balance().unwrap();
+//! assert_eq!(trial_balance.debits, trial_balance.credits);
+//! ```
+
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use {Decimal, ParseDecimalError};
+
+/// An amount posted to an account: a debit if positive, a credit if negative.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Posting {
+    pub account: String,
+    pub amount: Decimal,
+}
+
+impl Posting {
+    /// A debit of `amount` to `account`.
+    pub fn debit(account: &str, amount: Decimal) -> Posting {
+        Posting { account: account.to_string(), amount }
+    }
+
+    /// A credit of `amount` to `account`, i.e. a posting of `-amount`.
+    pub fn credit(account: &str, amount: Decimal) -> Posting {
+        Posting { account: account.to_string(), amount: Decimal::new(-amount.unscaled, amount.scale) }
+    }
+}
+
+/// A journal entry: postings that sum to zero, with a description.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub description: String,
+    pub postings: Vec<Posting>,
+}
+
+impl Entry {
+    pub fn new(description: &str, postings: Vec<Posting>) -> Entry {
+        Entry { description: description.to_string(), postings }
+    }
+}
+
+/// An account's balance after a posting to it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunningBalance {
+    /// The index in `Ledger::entries` of the entry the posting is part of.
+    pub entry: usize,
+    /// The amount posted to the account.
+    pub amount: Decimal,
+    pub balance: Decimal,
+}
+
+/// Accounts and the entries posted to them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ledger {
+    balances: BTreeMap<String, Decimal>,
+    entries: Vec<Entry>,
+}
+
+impl Ledger {
+    pub fn new() -> Ledger {
+        Ledger::default()
+    }
+
+    /// Opens an account with a zero balance. Names can't be empty or contain control
+    /// characters.
+    pub fn open_account(&mut self, name: &str) -> Result<(), LedgerError> {
+        self.check_account(name)?;
+        self.balances.insert(name.to_string(), Decimal::new(0, 0));
+        Ok(())
+    }
+
+    /// The open accounts' names, in order.
+    pub fn accounts(&self) -> impl Iterator<Item = &str> {
+        self.balances.keys().map(|name| &name[..])
+    }
+
+    /// An account's balance, or `None` if it isn't open.
+    pub fn balance(&self, account: &str) -> Option<Decimal> {
+        self.balances.get(account).cloned()
+    }
+
+    /// The entries posted so far, oldest first.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Posts an entry, updating its accounts' balances, unless it has fewer than two
+    /// postings, names an account that isn't open, doesn't sum to zero, or would
+    /// overflow a balance. A rejected entry changes nothing.
+    pub fn post(&mut self, entry: Entry) -> Result<(), LedgerError> {
+        let balances = self.check_entry(&entry)?;
+        self.apply(entry, balances);
+        Ok(())
+    }
+
+    /// An account's balance after each posting to it, oldest first, or `None` if it isn't
+    /// open. An entry that posts to the account more than once appears once per posting.
+    pub fn running_balances(&self, account: &str) -> Option<Vec<RunningBalance>> {
+        self.balances.get(account)?;
+        let mut balance = Decimal::new(0, 0);
+        let mut history = vec![];
+        for (i, entry) in self.entries.iter().enumerate() {
+            for posting in entry.postings.iter().filter(|p| p.account == account) {
+                // These are the sums check_entry made, in the same order, so they fit.
+                balance = balance + posting.amount;
+                history.push(RunningBalance { entry: i, amount: posting.amount, balance });
+            }
+        }
+        Some(history)
+    }
+
+    /// Every account's balance as a debit or credit, with the totals of each, which are
+    /// always equal.
+    pub fn trial_balance(&self) -> Result<TrialBalance, LedgerError> {
+        let zero = Decimal::new(0, 0);
+        let mut report = TrialBalance { rows: vec![], debits: zero, credits: zero };
+        for (account, &balance) in &self.balances {
+            let (debit, credit) = if balance.unscaled < 0 {
+                (zero, Decimal::new(-balance.unscaled, balance.scale))
+            } else {
+                (balance, zero)
+            };
+            report.debits = report.debits.try_add(debit).map_err(|_| LedgerError::Overflow)?;
+            report.credits = report.credits.try_add(credit).map_err(|_| LedgerError::Overflow)?;
+            report.rows.push(TrialBalanceRow { account: account.clone(), debit, credit });
+        }
+        Ok(report)
+    }
+
+    /// Reads a ledger from a journal, as written by a `Journal` or `write_journal`.
+    pub fn read_journal<R: Read>(mut input: R) -> Result<Ledger, JournalError> {
+        let mut text = String::new();
+        input.read_to_string(&mut text).map_err(JournalError::Io)?;
+        let mut ledger = Ledger::new();
+        let line_count = text.lines().count();
+        for (i, line) in text.lines().enumerate() {
+            let error = |kind| JournalError::Line { line: i + 1, kind };
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if i + 1 == line_count && !text.ends_with('\n') {
+                // Every record ends with a newline, so this one was cut off mid-write.
+                return Err(error(LineErrorKind::Truncated));
+            }
+            let result = match parse_record(line).map_err(&error)? {
+                Record::Open(name) => ledger.open_account(&name),
+                Record::Entry(entry) => ledger.post(entry),
+            };
+            result.map_err(|e| error(LineErrorKind::Ledger(e)))?;
+        }
+        Ok(ledger)
+    }
+
+    /// Writes the whole ledger as a journal, accounts first.
+    pub fn write_journal<W: Write>(&self, mut out: W) -> io::Result<()> {
+        for account in self.balances.keys() {
+            out.write_all(format_open(account).as_bytes())?;
+        }
+        for entry in &self.entries {
+            out.write_all(format_entry(entry).as_bytes())?;
+        }
+        out.flush()
+    }
+
+    fn check_account(&self, name: &str) -> Result<(), LedgerError> {
+        if name.is_empty() || name.chars().any(char::is_control) {
+            Err(LedgerError::InvalidAccountName(name.to_string()))
+        } else if self.balances.contains_key(name) {
+            Err(LedgerError::DuplicateAccount(name.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks an entry can be posted, returning the balances it would leave its
+    /// accounts with.
+    fn check_entry(&self, entry: &Entry) -> Result<BTreeMap<String, Decimal>, LedgerError> {
+        if entry.postings.len() < 2 {
+            return Err(LedgerError::TooFewPostings);
+        }
+        let mut balances = BTreeMap::new();
+        let mut sum = Decimal::new(0, 0);
+        for posting in &entry.postings {
+            let balance = match balances.get(&posting.account) {
+                Some(&balance) => balance,
+                None => self.balance(&posting.account)
+                    .ok_or_else(|| LedgerError::UnknownAccount(posting.account.clone()))?,
+            };
+            let balance = balance.try_add(posting.amount).map_err(|_| LedgerError::Overflow)?;
+            balances.insert(posting.account.clone(), balance);
+            sum = sum.try_add(posting.amount).map_err(|_| LedgerError::Overflow)?;
+        }
+        if sum.unscaled != 0 {
+            return Err(LedgerError::Unbalanced(sum));
+        }
+        Ok(balances)
+    }
+
+    fn apply(&mut self, entry: Entry, balances: BTreeMap<String, Decimal>) {
+        self.balances.extend(balances);
+        self.entries.push(entry);
+    }
+}
+
+/// A ledger's account balances, split into debits and credits.
+///
+/// It displays as a table with a total row, e.g.
+///
+/// ```text
+/// account        debit  credit
+/// assets:cash     7.99
+/// income:sales           19.99
+/// expenses:rent  12.00
+/// total          19.99   19.99
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrialBalance {
+    pub rows: Vec<TrialBalanceRow>,
+    pub debits: Decimal,
+    pub credits: Decimal,
+}
+
+/// One account's balance in a trial balance. At most one of `debit` and `credit` is
+/// nonzero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrialBalanceRow {
+    pub account: String,
+    pub debit: Decimal,
+    pub credit: Decimal,
+}
+
+impl fmt::Display for TrialBalance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let show = |d: Decimal| if d.unscaled == 0 { String::new() } else { d.to_string() };
+        let mut rows = vec![("account".to_string(), "debit".to_string(), "credit".to_string())];
+        rows.extend(self.rows.iter().map(|r| (r.account.clone(), show(r.debit), show(r.credit))));
+        rows.push(("total".to_string(), self.debits.to_string(), self.credits.to_string()));
+        let width = |column: fn(&(String, String, String)) -> &String| {
+            rows.iter().map(|r| column(r).chars().count()).max().unwrap_or(0)
+        };
+        let widths = (width(|r| &r.0), width(|r| &r.1), width(|r| &r.2));
+        for (account, debit, credit) in &rows {
+            let line = format!("{:<a$}  {:>d$}  {:>c$}", account, debit, credit,
+                               a = widths.0, d = widths.1, c = widths.2);
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// A ledger that appends every change to a journal before making it.
+///
+/// If an append fails, the journal may end in part of a record, so every later change
+/// is refused with `JournalError::Failed` rather than written after it.
+#[derive(Debug)]
+pub struct Journal<W: Write> {
+    ledger: Ledger,
+    out: W,
+    failed: bool,
+}
+
+impl Journal<File> {
+    /// Reads the ledger in the journal file at `path`, creating the file if there's
+    /// none, and opens it for appending.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Journal<File>, JournalError> {
+        let mut file = OpenOptions::new().read(true).append(true).create(true).open(path)
+            .map_err(JournalError::Io)?;
+        let ledger = Ledger::read_journal(&mut file)?;
+        Ok(Journal::new(ledger, file))
+    }
+}
+
+impl<W: Write> Journal<W> {
+    /// A journal appending to `out`, which should already hold `ledger`'s records, e.g.
+    /// because it's the file `ledger` was read from.
+    pub fn new(ledger: Ledger, out: W) -> Journal<W> {
+        Journal { ledger, out, failed: false }
+    }
+
+    pub fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    /// Opens an account, once it's recorded in the journal.
+    pub fn open_account(&mut self, name: &str) -> Result<(), JournalError> {
+        self.ledger.check_account(name).map_err(JournalError::Ledger)?;
+        self.append(&format_open(name))?;
+        self.ledger.open_account(name).map_err(JournalError::Ledger)
+    }
+
+    /// Posts an entry, once it's recorded in the journal. An entry the ledger rejects
+    /// isn't recorded.
+    pub fn post(&mut self, entry: Entry) -> Result<(), JournalError> {
+        let balances = self.ledger.check_entry(&entry).map_err(JournalError::Ledger)?;
+        self.append(&format_entry(&entry))?;
+        self.ledger.apply(entry, balances);
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> (Ledger, W) {
+        (self.ledger, self.out)
+    }
+
+    fn append(&mut self, record: &str) -> Result<(), JournalError> {
+        if self.failed {
+            return Err(JournalError::Failed);
+        }
+        let result = self.out.write_all(record.as_bytes()).and_then(|_| self.out.flush());
+        self.failed = result.is_err();
+        result.map_err(JournalError::Io)
+    }
+}
+
+enum Record {
+    Open(String),
+    Entry(Entry),
+}
+
+fn format_open(account: &str) -> String {
+    format!("open\t{}\n", escape(account))
+}
+
+fn format_entry(entry: &Entry) -> String {
+    let mut record = format!("entry\t{}", escape(&entry.description));
+    for posting in &entry.postings {
+        record.push_str(&format!("\t{}\t{}", escape(&posting.account), posting.amount));
+    }
+    record.push('\n');
+    record
+}
+
+fn parse_record(line: &str) -> Result<Record, LineErrorKind> {
+    let fields = line.split('\t').map(unescape).collect::<Result<Vec<_>, _>>()?;
+    match (&fields[0][..], fields.len()) {
+        ("open", 2) => Ok(Record::Open(fields[1].clone())),
+        ("entry", n) if n % 2 == 0 => {
+            let postings = fields[2..].chunks(2)
+                .map(|pair| Ok(Posting { account: pair[0].clone(),
+                                         amount: pair[1].parse().map_err(LineErrorKind::Amount)? }))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Record::Entry(Entry { description: fields[1].clone(), postings }))
+        }
+        ("open", n) | ("entry", n) => Err(LineErrorKind::FieldCount(n)),
+        (other, _) => Err(LineErrorKind::UnknownRecord(other.to_string())),
+    }
+}
+
+fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(field: &str) -> Result<String, LineErrorKind> {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        unescaped.push(match chars.next() {
+            Some('\\') => '\\',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            other => return Err(LineErrorKind::Escape(other)),
+        });
+    }
+    Ok(unescaped)
+}
+
+/// The error returned when a ledger rejects an account or entry.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LedgerError {
+    /// An account name that's empty or contains control characters.
+    InvalidAccountName(String),
+    DuplicateAccount(String),
+    UnknownAccount(String),
+    /// An entry with fewer than two postings.
+    TooFewPostings,
+    /// An entry whose postings don't sum to zero, but to this.
+    Unbalanced(Decimal),
+    /// A balance or total that doesn't fit in a `Decimal`.
+    Overflow,
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LedgerError::InvalidAccountName(ref name) => write!(f, "invalid account name '{}'", name),
+            LedgerError::DuplicateAccount(ref name) => write!(f, "account '{}' is already open", name),
+            LedgerError::UnknownAccount(ref name) => write!(f, "no account named '{}'", name),
+            LedgerError::TooFewPostings => f.write_str("entry needs at least two postings"),
+            LedgerError::Unbalanced(sum) =>
+                write!(f, "entry doesn't balance: its postings sum to {}", sum),
+            LedgerError::Overflow => f.write_str("account balance overflowed"),
+        }
+    }
+}
+
+impl error::Error for LedgerError {}
+
+/// The error returned when reading or appending to a journal fails.
+#[derive(Debug)]
+pub enum JournalError {
+    Io(io::Error),
+    /// A change the ledger rejected, so it wasn't appended.
+    Ledger(LedgerError),
+    /// A line (counting from 1) of a journal being read that isn't a valid record.
+    Line { line: usize, kind: LineErrorKind },
+    /// A change refused because an earlier append failed.
+    Failed,
+}
+
+/// What's wrong with a line of a journal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineErrorKind {
+    UnknownRecord(String),
+    /// A record with the wrong number of fields; this many instead.
+    FieldCount(usize),
+    /// A backslash followed by this, or by nothing, which isn't an escape.
+    Escape(Option<char>),
+    Amount(ParseDecimalError),
+    /// A last line without a newline, as left by an interrupted write.
+    Truncated,
+    /// A record the ledger rejected when replaying it.
+    Ledger(LedgerError),
+}
+
+impl fmt::Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JournalError::Io(ref e) => write!(f, "failed to read or write journal: {}", e),
+            JournalError::Ledger(ref e) => write!(f, "{}", e),
+            JournalError::Failed => f.write_str("journal is unusable after a failed write"),
+            JournalError::Line { line, ref kind } => {
+                write!(f, "line {}: ", line)?;
+                match *kind {
+                    LineErrorKind::UnknownRecord(ref name) => write!(f, "unknown record '{}'", name),
+                    LineErrorKind::FieldCount(n) => write!(f, "wrong number of fields ({})", n),
+                    LineErrorKind::Escape(Some(c)) => write!(f, "invalid escape '\\{}'", c),
+                    LineErrorKind::Escape(None) => f.write_str("backslash at end of field"),
+                    LineErrorKind::Amount(ref e) => write!(f, "invalid amount: {}", e),
+                    LineErrorKind::Truncated => f.write_str("record is incomplete"),
+                    LineErrorKind::Ledger(ref e) => write!(f, "{}", e),
+                }
+            }
+        }
+    }
+}
+
+impl error::Error for JournalError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            JournalError::Io(ref e) => Some(e),
+            JournalError::Ledger(ref e) => Some(e),
+            JournalError::Line { kind: LineErrorKind::Amount(ref e), .. } => Some(e),
+            JournalError::Line { kind: LineErrorKind::Ledger(ref e), .. } => Some(e),
+            JournalError::Line { .. } | JournalError::Failed => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::io::{self, Write};
+    use std::process;
+
+    use super::{Entry, Journal, JournalError, Ledger, LedgerError, LineErrorKind, Posting,
+                RunningBalance};
+    use Decimal;
+
+    fn d(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    fn sale(amount: &str) -> Entry {
+        Entry::new("Sale", vec![Posting::debit("assets:cash", d(amount)),
+                                Posting::credit("income:sales", d(amount))])
+    }
+
+    fn ledger() -> Ledger {
+        let mut ledger = Ledger::new();
+        for account in &["assets:cash", "income:sales", "expenses:rent"] {
+            ledger.open_account(account).unwrap();
+        }
+        ledger
+    }
+
+    #[test]
+    fn posts_balanced_entries() {
+        let mut ledger = ledger();
+        ledger.post(sale("19.99")).unwrap();
+        ledger.post(Entry::new("Rent and a refund", vec![
+            Posting::debit("expenses:rent", d("12")),
+            Posting::credit("assets:cash", d("12")),
+            Posting::debit("income:sales", d("0.99")),
+            Posting::credit("assets:cash", d("0.99")),
+        ])).unwrap();
+        assert_eq!(Some(d("7.00")), ledger.balance("assets:cash"));
+        assert_eq!(Some(d("-19.00")), ledger.balance("income:sales"));
+        assert_eq!(Some(d("12")), ledger.balance("expenses:rent"));
+        assert_eq!(None, ledger.balance("assets:bank"));
+        assert_eq!(2, ledger.entries().len());
+        assert_eq!(vec!["assets:cash", "expenses:rent", "income:sales"],
+                   ledger.accounts().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn rejects_bad_entries_without_changing_anything() {
+        let mut ledger = ledger();
+        ledger.post(sale("5")).unwrap();
+        let before = ledger.clone();
+        let unbalanced = Entry::new("", vec![Posting::debit("assets:cash", d("10.00")),
+                                             Posting::credit("income:sales", d("9.99"))]);
+        assert_eq!(Err(LedgerError::Unbalanced(d("0.01"))), ledger.post(unbalanced));
+        let unknown = Entry::new("", vec![Posting::debit("assets:cash", d("1")),
+                                          Posting::credit("assets:bank", d("1"))]);
+        assert_eq!(Err(LedgerError::UnknownAccount("assets:bank".to_string())), ledger.post(unknown));
+        assert_eq!(Err(LedgerError::TooFewPostings),
+                   ledger.post(Entry::new("", vec![Posting::debit("assets:cash", d("0"))])));
+        let overflow = Entry::new("", vec![Posting::debit("assets:cash", Decimal::new(i64::MAX, 0)),
+                                           Posting::credit("income:sales", Decimal::new(i64::MAX, 0))]);
+        assert_eq!(Err(LedgerError::Overflow), ledger.post(overflow));
+        assert_eq!(before, ledger);
+
+        assert_eq!(Err(LedgerError::DuplicateAccount("assets:cash".to_string())),
+                   ledger.open_account("assets:cash"));
+        assert_eq!(Err(LedgerError::InvalidAccountName("a\tb".to_string())), ledger.open_account("a\tb"));
+        assert_eq!(Err(LedgerError::InvalidAccountName("".to_string())), ledger.open_account(""));
+    }
+
+    #[test]
+    fn balance_is_checked_exactly_across_scales() {
+        let mut ledger = ledger();
+        ledger.post(Entry::new("", vec![Posting::debit("assets:cash", d("1.50")),
+                                        Posting::credit("income:sales", d("1.5")),
+                                        Posting::debit("expenses:rent", d("0E+2"))])).unwrap();
+        assert_eq!(Err(LedgerError::Unbalanced(d("1E-16"))),
+                   ledger.post(Entry::new("", vec![Posting::debit("assets:cash", d("1.0000000000000001")),
+                                                   Posting::credit("income:sales", d("1"))])));
+    }
+
+    #[test]
+    fn running_balances() {
+        let mut ledger = ledger();
+        ledger.post(sale("10")).unwrap();
+        ledger.post(Entry::new("Rent", vec![Posting::debit("expenses:rent", d("4.50")),
+                                            Posting::credit("assets:cash", d("4.50"))])).unwrap();
+        ledger.post(sale("0.25")).unwrap();
+        assert_eq!(Some(vec![RunningBalance { entry: 0, amount: d("10"), balance: d("10") },
+                             RunningBalance { entry: 1, amount: d("-4.50"), balance: d("5.50") },
+                             RunningBalance { entry: 2, amount: d("0.25"), balance: d("5.75") }]),
+                   ledger.running_balances("assets:cash"));
+        assert_eq!(Some(vec![RunningBalance { entry: 1, amount: d("4.50"), balance: d("4.50") }]),
+                   ledger.running_balances("expenses:rent"));
+        assert_eq!(None, ledger.running_balances("assets:bank"));
+    }
+
+    #[test]
+    fn running_balances_follow_each_posting() {
+        let mut ledger = Ledger::new();
+        for account in &["x", "y", "z"] {
+            ledger.open_account(account).unwrap();
+        }
+        let nine = d("9E+18");
+        ledger.post(Entry::new("Out", vec![Posting::credit("x", nine),
+                                           Posting::debit("y", nine)])).unwrap();
+        // x nets 1.8E+19 here, which doesn't fit, but each balance along the way does.
+        ledger.post(Entry::new("Back", vec![Posting::debit("x", nine), Posting::credit("y", nine),
+                                            Posting::debit("x", nine), Posting::credit("z", nine)]))
+            .unwrap();
+        let balance = |unscaled| Decimal::new(unscaled, 0);
+        assert_eq!(Some(vec![RunningBalance { entry: 0, amount: d("-9E+18"),
+                                              balance: balance(-9_000_000_000_000_000_000) },
+                             RunningBalance { entry: 1, amount: nine, balance: balance(0) },
+                             RunningBalance { entry: 1, amount: nine,
+                                              balance: balance(9_000_000_000_000_000_000) }]),
+                   ledger.running_balances("x"));
+    }
+
+    #[test]
+    fn trial_balance() {
+        let mut ledger = ledger();
+        ledger.open_account("equity").unwrap();
+        ledger.post(sale("19.99")).unwrap();
+        ledger.post(Entry::new("Rent", vec![Posting::debit("expenses:rent", d("12.00")),
+                                            Posting::credit("assets:cash", d("12.00"))])).unwrap();
+        let report = ledger.trial_balance().unwrap();
+        assert_eq!((d("19.99"), d("19.99")), (report.debits, report.credits));
+        assert_eq!(vec![("assets:cash", d("7.99"), d("0")), ("equity", d("0"), d("0")),
+                        ("expenses:rent", d("12.00"), d("0")), ("income:sales", d("0"), d("19.99"))],
+                   report.rows.iter().map(|r| (&r.account[..], r.debit, r.credit)).collect::<Vec<_>>());
+        assert_eq!("account        debit  credit\n\
+                    assets:cash     7.99\n\
+                    equity\n\
+                    expenses:rent  12.00\n\
+                    income:sales           19.99\n\
+                    total          19.99   19.99\n",
+                   report.to_string());
+    }
+
+    #[test]
+    fn journal_round_trips() {
+        let mut journal = Journal::new(Ledger::new(), vec![]);
+        journal.open_account("assets:cash").unwrap();
+        journal.open_account("income:odd\\name").unwrap();
+        journal.post(Entry::new("Tab\there,\nnewline", vec![
+            Posting::debit("assets:cash", d("1.50")),
+            Posting::credit("income:odd\\name", d("1.5")),
+        ])).unwrap();
+        match journal.post(Entry::new("", vec![Posting::debit("assets:cash", d("1"))])) {
+            Err(JournalError::Ledger(LedgerError::TooFewPostings)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        let (ledger, out) = journal.into_inner();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!("open\tassets:cash\n\
+                    open\tincome:odd\\\\name\n\
+                    entry\tTab\\there,\\nnewline\tassets:cash\t1.50\tincome:odd\\\\name\t-1.5\n",
+                   text);
+        assert_eq!(ledger, Ledger::read_journal(text.as_bytes()).unwrap());
+
+        let mut copy = vec![];
+        ledger.write_journal(&mut copy).unwrap();
+        assert_eq!(text.as_bytes(), &copy[..]);
+    }
+
+    #[test]
+    fn journal_file_is_appended_to() {
+        let path = env::temp_dir().join(format!("decimal-ledger-{}.journal", process::id()));
+        let _ = fs::remove_file(&path);
+        {
+            let mut journal = Journal::open(&path).unwrap();
+            journal.open_account("assets:cash").unwrap();
+            journal.open_account("income:sales").unwrap();
+            journal.post(sale("2.00")).unwrap();
+        }
+        {
+            let mut journal = Journal::open(&path).unwrap();
+            assert_eq!(Some(d("2.00")), journal.ledger().balance("assets:cash"));
+            journal.post(sale("3")).unwrap();
+        }
+        let ledger = Journal::open(&path).unwrap().ledger().clone();
+        assert_eq!(Some(d("5.00")), ledger.balance("assets:cash"));
+        assert_eq!(2, ledger.entries().len());
+
+        // A record cut off mid-write is reported rather than silently dropped.
+        fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"entry\tSale").unwrap();
+        match Journal::open(&path) {
+            Err(JournalError::Line { line: 5, kind: LineErrorKind::Truncated }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    /// Accepts `limit` bytes, then fails every write.
+    struct FailingWriter {
+        written: Vec<u8>,
+        limit: usize,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.limit - self.written.len());
+            if n == 0 {
+                return Err(io::Error::other("disk full"));
+            }
+            self.written.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn refuses_changes_after_a_failed_append() {
+        let mut journal = Journal::new(Ledger::new(), FailingWriter { written: vec![], limit: 20 });
+        journal.open_account("assets:cash").unwrap();
+        match journal.open_account("income:sales") {
+            Err(JournalError::Io(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match journal.open_account("x") {
+            Err(JournalError::Failed) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        let (ledger, out) = journal.into_inner();
+        assert_eq!(b"open\tassets:cash\nope", &out.written[..]);
+        assert_eq!(vec!["assets:cash"], ledger.accounts().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn reports_bad_journal_lines() {
+        let error = |text: &str| match Ledger::read_journal(text.as_bytes()) {
+            Err(JournalError::Line { line, kind }) => (line, kind),
+            other => panic!("unexpected {:?}", other),
+        };
+        let opens = "# accounts\nopen\ta\n\nopen\tb\n";
+        assert_eq!((5, LineErrorKind::Ledger(LedgerError::Unbalanced(d("1")))),
+                   error(&format!("{}entry\t\ta\t2\tb\t-1\n", opens)));
+        assert_eq!((5, LineErrorKind::Ledger(LedgerError::UnknownAccount("c".to_string()))),
+                   error(&format!("{}entry\t\ta\t1\tc\t-1\n", opens)));
+        assert_eq!((5, LineErrorKind::FieldCount(5)), error(&format!("{}entry\t\ta\t1\tb\n", opens)));
+        assert_eq!((1, LineErrorKind::FieldCount(3)), error("open\ta\tb\n"));
+        assert_eq!((1, LineErrorKind::UnknownRecord("close".to_string())), error("close\ta\n"));
+        assert_eq!((1, LineErrorKind::Escape(Some('x'))), error("open\ta\\x\n"));
+        assert_eq!((1, LineErrorKind::Escape(None)), error("open\ta\\\n"));
+        assert_eq!((2, LineErrorKind::Ledger(LedgerError::DuplicateAccount("a".to_string()))),
+                   error("open\ta\nopen\ta\n"));
+        match error(&format!("{}entry\t\ta\t1.x\tb\t-1\n", opens)) {
+            (5, LineErrorKind::Amount(_)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        let message = Ledger::read_journal(format!("{}entry\t\ta\t2\tb\t-1\n", opens).as_bytes())
+            .unwrap_err().to_string();
+        assert_eq!("line 5: entry doesn't balance: its postings sum to 1", message);
+    }
+}
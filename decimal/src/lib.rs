@@ -25,13 +25,35 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 // THE SOFTWARE.
 
+#[cfg(feature = "arbitrary")]
+extern crate quickcheck;
+
 use std::ops;
 use std::cmp::Ordering;
+use std::convert::TryFrom;
 use std::fmt;
 use std::str;
 
-/// A simple decimal number type consisting of an unscaled `i64` and a `u32` scale
-/// (number of places to the right of the decimal point). Math operators are supported.
+pub mod column;
+pub mod compact;
+pub mod exchange;
+pub mod expr;
+pub mod ledger;
+pub mod percent;
+pub mod quantity;
+pub mod range;
+pub mod ratio;
+pub mod stats;
+pub mod tax;
+pub mod wire;
+pub mod words;
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+
+/// A simple decimal number type consisting of an unscaled `i64` and an `i32` scale
+/// (number of places to the right of the decimal point). A negative scale multiplies the
+/// unscaled value by a power of ten, so large round numbers fit without overflowing
+/// the unscaled value. Math operators are supported.
 ///
 /// # Examples
 /// ```
@@ -42,19 +64,23 @@ use std::str;
 /// assert_eq!(format!("{}", three_fifty * two), "7.00");
 /// assert_eq!(format!("{}", three_fifty + two), "5.50");
 /// assert_eq!(format!("{}", three_fifty / two), "1.75");
+///
+/// let market_cap = Decimal::new(12, -19); // 12 * 10^19
+/// assert_eq!(format!("{}", market_cap), "1.2E+20");
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Decimal {
     pub unscaled: i64,
-    pub scale: u32
+    pub scale: i32
 }
 
 impl Decimal {
-    pub fn new(unscaled: i64, scale: u32) -> Decimal {
-        Decimal { unscaled: unscaled, scale: scale }
+    pub fn new(unscaled: i64, scale: i32) -> Decimal {
+        Decimal { unscaled, scale }
     }
 
-    /// Add or truncate places to the right of the decimal.
+    /// Add or truncate places to the right of the decimal. A negative scale truncates
+    /// places to the left of it.
     ///
     /// # Examples
     /// ```
@@ -63,14 +89,443 @@ impl Decimal {
     /// assert_eq!(Decimal::new(10, 1).adjust_scale(2), Decimal::new(100, 2));
     /// assert_eq!(Decimal::new(1000, 3).adjust_scale(2), Decimal::new(100, 2));
     /// assert_eq!(Decimal::new(125, 2).adjust_scale(1), Decimal::new(12, 1));
+    /// assert_eq!(Decimal::new(125, 0).adjust_scale(-1), Decimal::new(12, -1));
+    /// assert_eq!(Decimal::new(12, -1).adjust_scale(0), Decimal::new(120, 0));
     /// ```
-    pub fn adjust_scale(&self, new_scale: u32) -> Decimal {
+    pub fn adjust_scale(&self, new_scale: i32) -> Decimal {
         match self.scale.cmp(&new_scale) {
-            Ordering::Equal => self.clone(),
-            Ordering::Greater => Decimal::new(downscale(&self.unscaled, self.scale - new_scale), new_scale),
-            Ordering::Less => Decimal::new(upscale(&self.unscaled, new_scale - self.scale), new_scale)
+            Ordering::Equal => *self,
+            Ordering::Greater => Decimal::new(downscale(&self.unscaled, scale_diff(self.scale, new_scale)), new_scale),
+            Ordering::Less => Decimal::new(upscale(&self.unscaled, scale_diff(new_scale, self.scale)), new_scale)
+        }
+    }
+
+    /// Like `adjust_scale`, but dropped places are rounded according to `mode` rather
+    /// than truncated.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{Decimal, RoundingMode};
+    /// assert_eq!(Decimal::new(125, 2).round(1, RoundingMode::HalfUp), Decimal::new(13, 1));
+    /// assert_eq!(Decimal::new(125, 2).round(1, RoundingMode::HalfEven), Decimal::new(12, 1));
+    /// assert_eq!(Decimal::new(-121, 2).round(1, RoundingMode::Floor), Decimal::new(-13, 1));
+    /// assert_eq!(Decimal::new(1, 0).round(2, RoundingMode::Down), Decimal::new(100, 2));
+    /// assert_eq!(Decimal::new(1250, 0).round(-2, RoundingMode::HalfUp), Decimal::new(13, -2));
+    /// ```
+    pub fn round(&self, new_scale: i32, mode: RoundingMode) -> Decimal {
+        if new_scale >= self.scale {
+            return self.adjust_scale(new_scale);
+        }
+        let rounded = downscale_rounded(self.unscaled as i128, scale_diff(self.scale, new_scale), mode);
+        Decimal::new(rounded as i64, new_scale)
+    }
+
+    /// The number of significant digits: those in the unscaled value, so zero has one
+    /// and trailing zeros count.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::Decimal;
+    /// assert_eq!(Decimal::new(12345, 2).precision(), 5);
+    /// assert_eq!(Decimal::new(120, 4).precision(), 3);
+    /// assert_eq!(Decimal::new(12, -19).precision(), 2);
+    /// assert_eq!(Decimal::new(0, 2).precision(), 1);
+    /// ```
+    pub fn precision(&self) -> u32 {
+        let mut magnitude = self.unscaled.unsigned_abs();
+        let mut digits = 1;
+        while magnitude >= 10 {
+            magnitude /= 10;
+            digits += 1;
+        }
+        digits
+    }
+
+    /// Rounds to at most `digits` significant digits according to `mode`, dropping places
+    /// (to a negative scale, if need be) but never adding them.
+    ///
+    /// Panics if `digits` is zero or the scale would overflow.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{Decimal, RoundingMode};
+    /// let g = Decimal::new(980665, 5);
+    /// assert_eq!(g.round_to_significant(3, RoundingMode::HalfEven), Decimal::new(981, 2));
+    /// assert_eq!(Decimal::new(123456, 0).round_to_significant(2, RoundingMode::Down),
+    ///            Decimal::new(12, -4));
+    /// // A carry keeps the result to `digits` digits.
+    /// assert_eq!(Decimal::new(9996, 3).round_to_significant(3, RoundingMode::HalfUp),
+    ///            Decimal::new(100, 1));
+    /// assert_eq!(Decimal::new(25, 1).round_to_significant(3, RoundingMode::HalfUp),
+    ///            Decimal::new(25, 1));
+    /// ```
+    pub fn round_to_significant(&self, digits: u32, mode: RoundingMode) -> Decimal {
+        assert!(digits > 0, "attempt to round to zero significant digits");
+        let precision = self.precision();
+        if precision <= digits {
+            return *self;
+        }
+        let scale = self.scale.checked_sub((precision - digits) as i32)
+            .expect("attempt to round with scale overflow");
+        let rounded = self.round(scale, mode);
+        if rounded.precision() > digits {
+            // Rounding carried up to a power of ten, so the last digit is a zero.
+            let scale = scale.checked_sub(1).expect("attempt to round with scale overflow");
+            Decimal::new(rounded.unscaled / 10, scale)
+        } else {
+            rounded
+        }
+    }
+
+    /// Formats with exactly `digits` significant digits, as when reporting measurements:
+    /// the value is rounded to them according to `mode`, or padded with zeros to show
+    /// they're significant. Like `Display`, this uses scientific notation when the
+    /// digits stop short of the decimal point.
+    ///
+    /// Panics if `digits` is zero or the scale would overflow.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{Decimal, RoundingMode};
+    /// assert_eq!("2.50", Decimal::new(25, 1).to_significant(3, RoundingMode::HalfEven));
+    /// assert_eq!("0.00123", Decimal::new(1234, 6).to_significant(3, RoundingMode::HalfEven));
+    /// assert_eq!("1.23E+4", Decimal::new(12345, 0).to_significant(3, RoundingMode::HalfEven));
+    /// assert_eq!("120.0", Decimal::new(120, 0).to_significant(4, RoundingMode::HalfEven));
+    /// ```
+    pub fn to_significant(&self, digits: u32, mode: RoundingMode) -> String {
+        let rounded = self.round_to_significant(digits, mode);
+        let mut text = rounded.to_string();
+        let missing = (digits - rounded.precision()) as usize;
+        if missing > 0 {
+            let mantissa_end = text.find('E').unwrap_or(text.len());
+            let point = if text[..mantissa_end].contains('.') { "" } else { "." };
+            text.insert_str(mantissa_end, &format!("{}{}", point, "0".repeat(missing)));
+        }
+        text
+    }
+
+    /// Whether this decimal fits a SQL `DECIMAL(precision, scale)` column exactly: it
+    /// needs no more than `scale` places after the point, and no more than
+    /// `precision - scale` digits before it. A `scale` over `precision` fits nothing.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::Decimal;
+    /// assert!(Decimal::new(99999, 2).fits_sql_decimal(5, 2));
+    /// assert!(Decimal::new(1500, 3).fits_sql_decimal(3, 2)); // 1.500 is 1.50
+    /// assert!(!Decimal::new(100000, 2).fits_sql_decimal(5, 2)); // 1000.00
+    /// assert!(!Decimal::new(1234, 3).fits_sql_decimal(5, 2)); // 1.234
+    /// ```
+    pub fn fits_sql_decimal(&self, precision: u32, scale: u32) -> bool {
+        if scale > precision {
+            return false;
+        }
+        if self.unscaled == 0 {
+            return true;
+        }
+        let mut trailing_zeros = 0;
+        let mut unscaled = self.unscaled;
+        while unscaled % 10 == 0 {
+            unscaled /= 10;
+            trailing_zeros += 1;
+        }
+        let places_needed = self.scale as i64 - trailing_zeros;
+        let whole_digits = (self.precision() as i64 - self.scale as i64).max(0);
+        places_needed <= scale as i64 && whole_digits <= (precision - scale) as i64
+    }
+
+    /// Multiplies by `other`, rounding the exact product to `scale` according to `mode`.
+    /// Unlike `*`, the intermediate product can't overflow, only the rounded result.
+    ///
+    /// Panics if the result overflows.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{Decimal, RoundingMode};
+    /// let price = Decimal::new(1999, 2);
+    /// let rate = Decimal::new(825, 4);
+    /// assert_eq!(price.mul_round(rate, 2, RoundingMode::HalfUp), Decimal::new(165, 2));
+    /// assert_eq!(price.mul_round(rate, 2, RoundingMode::Down), Decimal::new(164, 2));
+    /// ```
+    pub fn mul_round(&self, other: Decimal, scale: i32, mode: RoundingMode) -> Decimal {
+        let product = self.unscaled as i128 * other.unscaled as i128;
+        let product_scale = self.scale as i64 + other.scale as i64;
+        let unscaled = if product_scale >= scale as i64 {
+            let down_by = (product_scale - scale as i64).min(u32::MAX as i64) as u32;
+            Some(downscale_rounded(product, down_by, mode))
+        } else if product == 0 {
+            Some(0)
+        } else {
+            u32::try_from(scale as i64 - product_scale).ok()
+                .and_then(|up_by| 10i128.checked_pow(up_by))
+                .and_then(|factor| product.checked_mul(factor))
+        };
+        let unscaled = unscaled.and_then(|u| i64::try_from(u).ok())
+            .expect("attempt to multiply with overflow");
+        Decimal::new(unscaled, scale)
+    }
+
+    /// Rounds to a multiple of `increment` (e.g. a tick size of `0.25`) according to
+    /// `mode`. The result has `increment`'s scale. The sign of `increment` is ignored.
+    ///
+    /// Panics if `increment` is zero or the result overflows.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{Decimal, RoundingMode};
+    /// let tick = Decimal::new(25, 2);
+    /// assert_eq!(Decimal::new(1030, 2).quantize_to_increment(tick, RoundingMode::HalfEven),
+    ///            Decimal::new(1025, 2));
+    /// assert_eq!(Decimal::new(103, 1).quantize_to_increment(tick, RoundingMode::Ceiling),
+    ///            Decimal::new(1050, 2));
+    /// assert_eq!(Decimal::new(1234, 0).quantize_to_increment(Decimal::new(1, -2), RoundingMode::Down),
+    ///            Decimal::new(12, -2));
+    /// ```
+    pub fn quantize_to_increment(&self, increment: Decimal, mode: RoundingMode) -> Decimal {
+        assert!(increment.unscaled != 0, "quantization increment must not be zero");
+        if self.unscaled == 0 {
+            return Decimal::new(0, increment.scale);
+        }
+        let step = increment.unscaled.unsigned_abs() as i128;
+        let count = if increment.scale >= self.scale {
+            let n = 10i128.checked_pow(scale_diff(increment.scale, self.scale))
+                .and_then(|factor| (self.unscaled as i128).checked_mul(factor))
+                .expect("attempt to quantize with overflow");
+            divide_rounded(n, step, mode)
+        } else {
+            // As in downscale_rounded, an increment too large to represent at our scale
+            // can be stood in for by i128::MAX.
+            let step = 10i128.checked_pow(scale_diff(self.scale, increment.scale))
+                .and_then(|factor| step.checked_mul(factor))
+                .unwrap_or(i128::MAX);
+            divide_rounded(self.unscaled as i128, step, mode)
+        };
+        let unscaled = count.checked_mul(step).and_then(|u| i64::try_from(u).ok())
+            .expect("attempt to quantize with overflow");
+        Decimal::new(unscaled, increment.scale)
+    }
+
+    /// Whether this is an exact multiple of `increment`, e.g. a valid price for a given
+    /// tick size. Only zero is a multiple of zero.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::Decimal;
+    /// let tick = Decimal::new(25, 2);
+    /// assert!(Decimal::new(1075, 2).is_multiple_of(tick));
+    /// assert!(Decimal::new(11, 0).is_multiple_of(tick));
+    /// assert!(!Decimal::new(1080, 2).is_multiple_of(tick));
+    /// ```
+    pub fn is_multiple_of(&self, increment: Decimal) -> bool {
+        if increment.unscaled == 0 {
+            return self.unscaled == 0;
+        }
+        let step = increment.unscaled.unsigned_abs() as i128;
+        if increment.scale >= self.scale {
+            let factor = pow10_mod(scale_diff(increment.scale, self.scale), step);
+            (self.unscaled as i128 % step) * factor % step == 0
+        } else {
+            match 10i128.checked_pow(scale_diff(self.scale, increment.scale))
+                .and_then(|factor| step.checked_mul(factor)) {
+                Some(step) => self.unscaled as i128 % step == 0,
+                None => self.unscaled == 0,
+            }
+        }
+    }
+
+    /// Like `+`, but returns an error rather than panicking when the result doesn't fit.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{ArithmeticError, Decimal};
+    /// assert_eq!(Decimal::new(1, 0).try_add(Decimal::new(25, 2)), Ok(Decimal::new(125, 2)));
+    /// assert_eq!(Decimal::new(i64::MAX, 0).try_add(Decimal::new(1, 0)),
+    ///            Err(ArithmeticError::Overflow));
+    /// ```
+    pub fn try_add(&self, other: Decimal) -> Result<Decimal, ArithmeticError> {
+        let (a, b, scale) = self.try_align(&other)?;
+        a.checked_add(b).map(|unscaled| Decimal::new(unscaled, scale))
+            .ok_or(ArithmeticError::Overflow)
+    }
+
+    /// Like `-`, but returns an error rather than panicking when the result doesn't fit.
+    pub fn try_sub(&self, other: Decimal) -> Result<Decimal, ArithmeticError> {
+        let (a, b, scale) = self.try_align(&other)?;
+        a.checked_sub(b).map(|unscaled| Decimal::new(unscaled, scale))
+            .ok_or(ArithmeticError::Overflow)
+    }
+
+    /// Like `*`, but returns an error rather than panicking when the result doesn't fit.
+    pub fn try_mul(&self, other: Decimal) -> Result<Decimal, ArithmeticError> {
+        let unscaled = self.unscaled.checked_mul(other.unscaled).ok_or(ArithmeticError::Overflow)?;
+        let scale = self.scale.checked_add(other.scale).ok_or(ArithmeticError::ScaleOverflow)?;
+        Ok(Decimal::new(unscaled, scale))
+    }
+
+    /// Divides by `other`, rounding the exact quotient to `scale` according to `mode`,
+    /// where `/` truncates at the difference of the scales.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{ArithmeticError, Decimal, RoundingMode};
+    /// let third = Decimal::new(1, 0).div_round(Decimal::new(3, 0), 4, RoundingMode::HalfEven);
+    /// assert_eq!(third, Ok(Decimal::new(3333, 4)));
+    /// assert_eq!(Decimal::new(1, 0).div_round(Decimal::new(0, 0), 4, RoundingMode::HalfEven),
+    ///            Err(ArithmeticError::DivisionByZero));
+    /// ```
+    pub fn div_round(&self, other: Decimal, scale: i32, mode: RoundingMode)
+                     -> Result<Decimal, ArithmeticError> {
+        if other.unscaled == 0 {
+            return Err(ArithmeticError::DivisionByZero);
+        }
+        divide_to_scale(self.unscaled as i128, self.scale as i64, other.unscaled as i128,
+                        other.scale as i64, scale, mode)
+    }
+
+    /// Both unscaled values at the wider of the two scales, or `Overflow` if one doesn't fit.
+    fn try_align(&self, other: &Decimal) -> Result<(i64, i64, i32), ArithmeticError> {
+        let scale = self.scale.max(other.scale);
+        let a = checked_upscale(self.unscaled, scale_diff(scale, self.scale));
+        let b = checked_upscale(other.unscaled, scale_diff(scale, other.scale));
+        match (a, b) {
+            (Some(a), Some(b)) => Ok((a, b, scale)),
+            _ => Err(ArithmeticError::Overflow),
         }
     }
+
+    /// Like `/`, but returns an error rather than panicking when `other` is zero or the
+    /// result doesn't fit.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{ArithmeticError, Decimal};
+    /// assert_eq!(Decimal::new(685, 2).try_div(Decimal::new(5, 1)), Ok(Decimal::new(137, 1)));
+    /// assert_eq!(Decimal::new(1, 0).try_div(Decimal::new(0, 2)),
+    ///            Err(ArithmeticError::DivisionByZero));
+    /// ```
+    pub fn try_div(&self, other: Decimal) -> Result<Decimal, ArithmeticError> {
+        let s = self.align_dividend(&other)?;
+        let unscaled = s.unscaled.checked_div(other.unscaled).ok_or(ArithmeticError::Overflow)?;
+        let scale = s.scale.checked_sub(other.scale).ok_or(ArithmeticError::ScaleOverflow)?;
+        Ok(Decimal::new(unscaled, scale))
+    }
+
+    /// Like `%`, but returns an error rather than panicking when `other` is zero or the
+    /// result doesn't fit.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{ArithmeticError, Decimal};
+    /// assert_eq!(Decimal::new(425, 2).try_rem(Decimal::new(200, 2)), Ok(Decimal::new(25, 2)));
+    /// assert_eq!(Decimal::new(1, 0).try_rem(Decimal::new(0, 0)),
+    ///            Err(ArithmeticError::DivisionByZero));
+    /// ```
+    pub fn try_rem(&self, other: Decimal) -> Result<Decimal, ArithmeticError> {
+        let s = self.align_dividend(&other)?;
+        // Only i64::MIN % -1 wraps, and its remainder really is 0.
+        Ok(Decimal::new(s.unscaled.wrapping_rem(other.unscaled), s.scale))
+    }
+
+    /// Returns `(self / other, self % other)`, aligning scales only once.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::Decimal;
+    /// let (q, r) = Decimal::new(-75, 1).div_rem(Decimal::new(2, 0));
+    /// assert_eq!((q, r), (Decimal::new(-37, 1), Decimal::new(-1, 1)));
+    /// ```
+    pub fn div_rem(&self, other: Decimal) -> (Decimal, Decimal) {
+        let s = self.aligned_to(&other);
+        (Decimal::new(s.unscaled / other.unscaled, s.scale - other.scale),
+         Decimal::new(s.unscaled % other.unscaled, s.scale))
+    }
+
+    /// Euclidean division: the quotient for which `rem_euclid` is never negative.
+    /// The quotient has the same scale `/` would give it.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::Decimal;
+    /// assert_eq!(Decimal::new(-7, 0).div_euclid(Decimal::new(2, 0)), Decimal::new(-4, 0));
+    /// assert_eq!(Decimal::new(-7, 0).div_euclid(Decimal::new(-2, 0)), Decimal::new(4, 0));
+    /// ```
+    pub fn div_euclid(&self, other: Decimal) -> Decimal {
+        let s = self.aligned_to(&other);
+        Decimal::new(s.unscaled.div_euclid(other.unscaled), s.scale - other.scale)
+    }
+
+    /// The remainder of `div_euclid`, which is always between zero (inclusive) and the
+    /// magnitude of `other` (exclusive).
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::Decimal;
+    /// assert_eq!(Decimal::new(-7, 0).rem_euclid(Decimal::new(2, 0)), Decimal::new(1, 0));
+    /// assert_eq!(Decimal::new(-75, 1).rem_euclid(Decimal::new(-2, 0)), Decimal::new(1, 1));
+    /// ```
+    pub fn rem_euclid(&self, other: Decimal) -> Decimal {
+        let s = self.aligned_to(&other);
+        Decimal::new(s.unscaled.rem_euclid(other.unscaled), s.scale)
+    }
+
+    /// Division rounding the quotient toward negative infinity, so the matching remainder
+    /// (`self - quotient * other`) takes the sign of `other`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::Decimal;
+    /// assert_eq!(Decimal::new(-7, 0).div_floor(Decimal::new(2, 0)), Decimal::new(-4, 0));
+    /// assert_eq!(Decimal::new(-7, 0).div_floor(Decimal::new(-2, 0)), Decimal::new(3, 0));
+    /// ```
+    pub fn div_floor(&self, other: Decimal) -> Decimal {
+        let s = self.aligned_to(&other);
+        let mut quotient = s.unscaled / other.unscaled;
+        if s.unscaled % other.unscaled != 0 && (s.unscaled < 0) != (other.unscaled < 0) {
+            quotient -= 1;
+        }
+        Decimal::new(quotient, s.scale - other.scale)
+    }
+
+    /// Brings a dividend up to at least the divisor's scale, panicking on overflow.
+    fn aligned_to(&self, divisor: &Decimal) -> Decimal {
+        if divisor.scale > self.scale {
+            self.adjust_scale(divisor.scale)
+        } else {
+            *self
+        }
+    }
+
+    /// Brings a dividend up to at least the divisor's scale, as `/` and `%` do.
+    fn align_dividend(&self, divisor: &Decimal) -> Result<Decimal, ArithmeticError> {
+        if divisor.unscaled == 0 {
+            return Err(ArithmeticError::DivisionByZero);
+        }
+        if divisor.scale <= self.scale {
+            return Ok(*self);
+        }
+        checked_upscale(self.unscaled, scale_diff(divisor.scale, self.scale))
+            .map(|unscaled| Decimal::new(unscaled, divisor.scale))
+            .ok_or(ArithmeticError::Overflow)
+    }
+}
+
+/// How to treat the places dropped when a `Decimal` loses scale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RoundingMode {
+    /// Toward zero, i.e., truncate (what `adjust_scale` does).
+    Down,
+    /// Away from zero.
+    Up,
+    /// Toward negative infinity.
+    Floor,
+    /// Toward positive infinity.
+    Ceiling,
+    /// To the nearest neighbor, with ties going away from zero.
+    HalfUp,
+    /// To the nearest neighbor, with ties going toward zero.
+    HalfDown,
+    /// To the nearest neighbor, with ties going to the even one ("banker's rounding").
+    HalfEven,
 }
 
 /// `Decimal` is only `PartialOrd`, not `Ord`, because its ordering is not antisymmetric,
@@ -88,13 +543,27 @@ impl Decimal {
 /// assert!(one > two_tenths);
 /// let one_point_oh = Decimal::new(10, 1);
 /// assert_eq!(::std::cmp::Ordering::Equal, one.partial_cmp(&one_point_oh).unwrap());
+/// let ten = Decimal::new(1, -1);
+/// assert!(ten > two);
 /// ```
 impl PartialOrd for Decimal {
     fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
-        Some(match self.scale.cmp(&other.scale) {
-            Ordering::Equal => self.unscaled.cmp(&other.unscaled),
-            Ordering::Greater => self.unscaled.cmp(&upscale(&other.unscaled, self.scale - other.scale)),
-            Ordering::Less => upscale(&self.unscaled, other.scale - self.scale).cmp(&other.unscaled)
+        let by_sign = self.unscaled.signum().cmp(&other.unscaled.signum());
+        if by_sign != Ordering::Equal || self.unscaled == 0 {
+            return Some(by_sign);
+        }
+        // With the same sign, the one whose leading digit is further left is larger in
+        // magnitude, so only scales within 19 of each other ever need aligning.
+        let exponent = |d: &Decimal| d.precision() as i64 - d.scale as i64;
+        Some(match exponent(self).cmp(&exponent(other)) {
+            Ordering::Equal => {
+                // Both have at most 19 digits, so aligning multiplies by at most 10^18.
+                let scale = self.scale.max(other.scale);
+                let widen = |d: &Decimal| d.unscaled as i128 * 10i128.pow(scale_diff(scale, d.scale));
+                widen(self).cmp(&widen(other))
+            }
+            ordering if self.unscaled < 0 => ordering.reverse(),
+            ordering => ordering,
         })
     }
 }
@@ -114,60 +583,112 @@ impl PartialOrd for Decimal {
 /// # use decimal::Decimal;
 /// assert_eq!(Decimal::new(-100, 2), "-1.00".parse().unwrap());
 /// ```
+/// With an exponent, as `Display` writes decimals with negative scales:
+///
+/// ```
+/// # use decimal::Decimal;
+/// assert_eq!(Decimal::new(12, -19), "1.2E+20".parse().unwrap());
+/// assert_eq!(Decimal::new(15, 4), "1.5e-3".parse().unwrap());
+/// ```
 impl str::FromStr for Decimal {
     type Err = ParseDecimalError;
     fn from_str(s: &str) -> Result<Decimal, ParseDecimalError> {
         use DecimalErrorKind::*;
-        let mut unscaled: i64 = 0;
-        let mut scale: u32 = 0;
-        let mut index: u32 = 0;
+        let invalid = |c, byte_index, char_index| ParseDecimalError::new(
+            InvalidChar { character: c, byte_index, char_index });
+        let mut magnitude: u64 = 0;
+        let mut digits = 0;
+        let mut scale: i64 = 0;
+        let mut index: usize = 0;
         let mut negative = false;
         let mut seen_decimal = false;
-        for c in s.chars() {
+        let mut exponent_marker: Option<(char, usize, usize)> = None;
+        let mut exponent: i64 = 0;
+        let mut exponent_negative = false;
+        let mut exponent_digits = 0;
+        for (byte_index, c) in s.char_indices() {
             match c {
+                c if exponent_marker.is_some() => match c {
+                    '-' | '+' if exponent_marker.unwrap().2 + 1 == index =>
+                        exponent_negative = c == '-',
+                    c if c.is_ascii_digit() => {
+                        exponent = exponent.saturating_mul(10)
+                            .saturating_add(c.to_digit(10).unwrap() as i64);
+                        exponent_digits += 1;
+                    },
+                    c => return Err(invalid(c, byte_index, index))
+                },
                 '-' if index == 0 => negative = true,
-                '.' => seen_decimal = true,
-                c if c.is_digit(10) => {
-                    unscaled = (unscaled * 10) + c.to_digit(10).unwrap() as i64;
+                '.' if !seen_decimal => seen_decimal = true,
+                c if c.is_ascii_digit() => {
+                    magnitude = magnitude.checked_mul(10)
+                        .and_then(|m| m.checked_add(c.to_digit(10).unwrap() as u64))
+                        .ok_or_else(|| ParseDecimalError::new(Overflow))?;
+                    digits += 1;
                     if seen_decimal {
                         scale += 1;
                     }
                 },
-                c => return Err(ParseDecimalError::new(InvalidChar(c, index)))
+                'e' | 'E' if index > 0 => exponent_marker = Some((c, byte_index, index)),
+                c => return Err(invalid(c, byte_index, index))
             }
             index += 1;
         }
         if index == 0 {
-            Err(ParseDecimalError::new(Empty))
-        } else {
-            Ok(Decimal::new(if negative { -unscaled } else { unscaled }, scale))
+            return Err(ParseDecimalError::new(Empty));
         }
+        if digits == 0 {
+            return Err(ParseDecimalError::new(NoDigits));
+        }
+        if let Some((marker, byte_index, char_index)) = exponent_marker {
+            if exponent_digits == 0 {
+                return Err(invalid(marker, byte_index, char_index));
+            }
+            // The exponent saturated rather than overflowing, so the scale does too.
+            scale = scale.saturating_sub(if exponent_negative { -exponent } else { exponent });
+        }
+        if scale < i32::MIN as i64 || scale > i32::MAX as i64 {
+            return Err(ParseDecimalError::new(ExponentOutOfRange));
+        }
+        let unscaled = if negative { -(magnitude as i128) } else { magnitude as i128 };
+        i64::try_from(unscaled).map(|unscaled| Decimal::new(unscaled, scale as i32))
+            .map_err(|_| ParseDecimalError::new(Overflow))
     }
 }
 
+/// Decimals with negative scales are written in scientific notation, e.g. `1.2E+20`,
+/// rather than with a run of zeros that would parse back with a different scale.
 impl fmt::Display for Decimal {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         use std::fmt::Write;
         if self.scale == 0 {
-            try!(write!(fmt, "{}", self.unscaled));
+            write!(fmt, "{}", self.unscaled)?;
             return Ok(());
         }
         let mut unscaled_str = format!("{}", self.unscaled);
         if self.unscaled.is_negative() {
             let _ = unscaled_str.remove(0);
-            try!(fmt.write_char('-'));
+            fmt.write_char('-')?;
         }
+        if self.scale < 0 {
+            let exponent = unscaled_str.len() as i64 - 1 - self.scale as i64;
+            if unscaled_str.len() > 1 {
+                unscaled_str.insert(1, '.');
+            }
+            return write!(fmt, "{}E+{}", unscaled_str, exponent);
+        }
+        let scale = self.scale as u32;
         let unscaled_len = unscaled_str.len() as u32; // assume all chars are 1-byte.
-        if self.scale >= unscaled_len {
-            try!(fmt.write_char('0'));
-            try!(fmt.write_char('.'));
-            for _ in 0..(self.scale - unscaled_len) {
-                try!(fmt.write_char('0'));
+        if scale >= unscaled_len {
+            fmt.write_char('0')?;
+            fmt.write_char('.')?;
+            for _ in 0..(scale - unscaled_len) {
+                fmt.write_char('0')?;
             }
         } else {
-            unscaled_str.insert((unscaled_len - self.scale) as usize, '.');
+            unscaled_str.insert((unscaled_len - scale) as usize, '.');
         }
-        try!(fmt.write_str(&*unscaled_str));
+        fmt.write_str(&unscaled_str)?;
         Ok(())
     }
 }
@@ -220,11 +741,7 @@ impl ops::Mul<Decimal> for i64 {
 impl ops::Div for Decimal {
     type Output = Decimal;
     fn div(self, other: Decimal) -> Decimal {
-        let s = if other.scale > self.scale {
-            self.adjust_scale(other.scale)
-        } else {
-            self
-        };
+        let s = self.aligned_to(&other);
         Decimal::new(s.unscaled / other.unscaled, s.scale - other.scale)
     }
 }
@@ -232,31 +749,136 @@ impl ops::Div for Decimal {
 impl ops::Rem for Decimal {
     type Output = Decimal;
     fn rem(self, other: Decimal) -> Decimal {
-        let s = if other.scale > self.scale {
-            self.adjust_scale(other.scale)
-        } else {
-            self
-        };
+        let s = self.aligned_to(&other);
         Decimal::new(s.unscaled % other.unscaled, s.scale)
     }
 }
 
+/// Divides `n` by `d`, rounding any remainder according to `mode`.
+fn divide_rounded(n: i128, d: i128, mode: RoundingMode) -> i128 {
+    let quotient = n / d;
+    let remainder = n % d;
+    if remainder == 0 {
+        return quotient;
+    }
+    let negative = (n < 0) != (d < 0);
+    let away_from_zero = match mode {
+        RoundingMode::Down => false,
+        RoundingMode::Up => true,
+        RoundingMode::Floor => negative,
+        RoundingMode::Ceiling => !negative,
+        RoundingMode::HalfUp | RoundingMode::HalfDown | RoundingMode::HalfEven => {
+            match (remainder.unsigned_abs() * 2).cmp(&d.unsigned_abs()) {
+                Ordering::Less => false,
+                Ordering::Greater => true,
+                Ordering::Equal => match mode {
+                    RoundingMode::HalfUp => true,
+                    RoundingMode::HalfDown => false,
+                    _ => quotient % 2 != 0,
+                },
+            }
+        }
+    };
+    match (away_from_zero, negative) {
+        (false, _) => quotient,
+        (true, false) => quotient + 1,
+        (true, true) => quotient - 1,
+    }
+}
+
+/// The quotient of `n` at `n_scale` by `d` at `d_scale`, rounded to `scale` according to
+/// `mode`, or `Overflow` if it doesn't fit. `d` must not be zero.
+fn divide_to_scale(n: i128, n_scale: i64, d: i128, d_scale: i64, scale: i32, mode: RoundingMode)
+                   -> Result<Decimal, ArithmeticError> {
+    // The quotient's unscaled value is n * 10^places / d.
+    let places = scale as i64 + d_scale - n_scale;
+    let (numer, denom) = if places >= 0 {
+        let numer = u32::try_from(places).ok()
+            .and_then(|p| checked_upscale_i128(n, p))
+            .ok_or(ArithmeticError::Overflow)?;
+        (numer, d)
+    } else {
+        // As in downscale_rounded, a divisor too large to represent can be stood in
+        // for by i128::MAX (with the divisor's sign).
+        let denom = u32::try_from(-places).ok()
+            .and_then(|p| checked_upscale_i128(d, p))
+            .unwrap_or(if d < 0 { -i128::MAX } else { i128::MAX });
+        (n, denom)
+    };
+    i64::try_from(divide_rounded(numer, denom, mode))
+        .map(|unscaled| Decimal::new(unscaled, scale))
+        .map_err(|_| ArithmeticError::Overflow)
+}
+
+/// The greatest common divisor, which is positive unless both are zero.
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    i128::try_from(a).expect("attempt to compute greatest common divisor with overflow")
+}
+
+/// Drops `down_by` places from `n`, rounding according to `mode`.
+fn downscale_rounded(n: i128, down_by: u32, mode: RoundingMode) -> i128 {
+    // Past 10^38 the divisor can't be represented, but anything that large dwarfs the
+    // values we round, so i128::MAX stands in for it.
+    let divisor = 10i128.checked_pow(down_by).unwrap_or(i128::MAX);
+    divide_rounded(n, divisor, mode)
+}
+
+/// 10^exp modulo `m`, by repeated squaring so large exponents can't overflow.
+fn pow10_mod(mut exp: u32, m: i128) -> i128 {
+    let mut result = 1 % m;
+    let mut base = 10 % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % m;
+        }
+        base = base * base % m;
+        exp >>= 1;
+    }
+    result
+}
+
+/// The distance from scale `from` down to scale `to`, which must not be greater.
+fn scale_diff(from: i32, to: i32) -> u32 {
+    (from as i64 - to as i64) as u32
+}
+
+fn checked_upscale(n: i64, up_by: u32) -> Option<i64> {
+    if n == 0 {
+        return Some(0);
+    }
+    10i64.checked_pow(up_by).and_then(|factor| n.checked_mul(factor))
+}
+
+fn checked_upscale_i128(n: i128, up_by: u32) -> Option<i128> {
+    if n == 0 {
+        return Some(0);
+    }
+    10i128.checked_pow(up_by).and_then(|factor| n.checked_mul(factor))
+}
+
 fn downscale(n: &i64, down_by: u32) -> i64 {
-    let mut result = n.clone();
+    let mut result = *n;
     for _ in 0..down_by {
-        result = result / 10;
+        result /= 10;
     }
     result
 }
 
 fn upscale(n: &i64, up_by: u32) -> i64 {
-    let mut result = n.clone();
+    let mut result = *n;
     for _ in 0..up_by {
-        result = result * 10;
+        result *= 10;
     }
     result
 }
 
+/// The error returned when parsing a `Decimal` from a string fails.
 #[derive(Debug, Clone, PartialEq)]
 pub struct ParseDecimalError {
     kind: DecimalErrorKind,
@@ -266,7 +888,51 @@ pub struct ParseDecimalError {
 impl ParseDecimalError {
     fn new(kind: DecimalErrorKind) -> Self {
         let desc = kind.desc();
-        ParseDecimalError { kind: kind, desc: desc }
+        ParseDecimalError { kind, desc }
+    }
+
+    /// What went wrong, for callers that need to react to a particular failure.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{Decimal, DecimalErrorKind};
+    /// let err = "12.3x".parse::<Decimal>().unwrap_err();
+    /// match *err.kind() {
+    ///     DecimalErrorKind::InvalidChar { character, char_index, .. } =>
+    ///         assert_eq!(('x', 4), (character, char_index)),
+    ///     _ => panic!("unexpected error {}", err),
+    /// }
+    /// ```
+    pub fn kind(&self) -> &DecimalErrorKind {
+        &self.kind
+    }
+
+    /// The byte range of the input responsible for the error, if it can be pinned to
+    /// a single character.
+    pub fn span(&self) -> Option<ops::Range<usize>> {
+        match self.kind {
+            DecimalErrorKind::InvalidChar { character, byte_index, .. } =>
+                Some(byte_index..byte_index + character.len_utf8()),
+            _ => None,
+        }
+    }
+
+    /// Renders `input` (the string that failed to parse) with a caret under the offending
+    /// character and the error message beside it, e.g. for command-line output.
+    /// Errors with no `span` are rendered as the input followed by the message.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::Decimal;
+    /// let err = "12.3x".parse::<Decimal>().unwrap_err();
+    /// assert_eq!("12.3x\n    ^ invalid character 'x' found at index 4", err.annotate("12.3x"));
+    /// ```
+    pub fn annotate(&self, input: &str) -> String {
+        match self.kind {
+            DecimalErrorKind::InvalidChar { char_index, .. } =>
+                format!("{}\n{}^ {}", input, " ".repeat(char_index), self.desc),
+            _ => format!("{}\n{}", input, self.desc),
+        }
     }
 }
 
@@ -282,10 +948,28 @@ impl std::error::Error for ParseDecimalError {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-enum DecimalErrorKind {
+/// The reasons parsing a `Decimal` can fail. More may be added, so matches need a
+/// wildcard arm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecimalErrorKind {
+    /// The input was empty.
     Empty,
-    InvalidChar(char, u32),
+    /// A character that can't appear at its position. A dangling exponent marker, as in
+    /// `1e`, is reported as the marker itself.
+    InvalidChar {
+        character: char,
+        /// Offset in bytes, for slicing the input.
+        byte_index: usize,
+        /// Offset in characters, for pointing at it in a display.
+        char_index: usize,
+    },
+    /// The exponent puts the scale outside the range of an `i32`.
+    ExponentOutOfRange,
+    /// A sign, point or exponent with no digits before the exponent, as in `-` or `.e5`.
+    NoDigits,
+    /// The digits make a number too large for an `i64` unscaled value.
+    Overflow,
 }
 
 impl DecimalErrorKind {
@@ -293,14 +977,167 @@ impl DecimalErrorKind {
         match *self {
             DecimalErrorKind::Empty =>
                 "cannot parse decimal from empty string".to_string(),
-            DecimalErrorKind::InvalidChar(c, i) => {
-                format!("invalid character '{}' found at index {}", c, i)
+            DecimalErrorKind::InvalidChar { character, char_index, .. } => {
+                format!("invalid character '{}' found at index {}", character, char_index)
             }
+            DecimalErrorKind::ExponentOutOfRange =>
+                "exponent out of range for decimal".to_string(),
+            DecimalErrorKind::NoDigits =>
+                "no digits found in decimal".to_string(),
+            DecimalErrorKind::Overflow =>
+                "number too large for decimal".to_string(),
         }
 
     }
 }
 
+/// The ways a checked operation such as `try_div` can fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArithmeticError {
+    /// The divisor was zero.
+    DivisionByZero,
+    /// The unscaled value of the result (or of an operand being rescaled) doesn't fit
+    /// in an `i64`.
+    Overflow,
+    /// The scale of the result doesn't fit in an `i32`.
+    ScaleOverflow,
+    /// The result can't be represented without discarding digits.
+    Inexact,
+}
+
+impl fmt::Display for ArithmeticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            ArithmeticError::DivisionByZero => "decimal division by zero",
+            ArithmeticError::Overflow => "decimal unscaled value overflowed",
+            ArithmeticError::ScaleOverflow => "decimal scale overflowed",
+            ArithmeticError::Inexact => "decimal result not exactly representable",
+        })
+    }
+}
+
+impl std::error::Error for ArithmeticError {}
+
+/// Any error this crate produces, for callers that would rather handle one type.
+/// The specific error is available through `source()`.
+///
+/// # Examples
+/// ```
+/// # use decimal::{Decimal, Error};
+/// fn halve(s: &str) -> Result<Decimal, Error> {
+///     Ok(s.parse::<Decimal>()?.try_div(Decimal::new(2, 0))?)
+/// }
+/// assert_eq!(halve("5.00"), Ok(Decimal::new(250, 2)));
+/// assert!(halve("five").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    Parse(ParseDecimalError),
+    Arithmetic(ArithmeticError),
+    Wire(wire::WireError),
+    Stats(stats::StatsError),
+    ParseExpr(expr::ParseExprError),
+    Eval(expr::EvalError),
+    Exchange(exchange::ExchangeError),
+    Ledger(ledger::LedgerError),
+    ParsePercent(percent::ParsePercentError),
+    ParseQuantity(quantity::ParseQuantityError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Error::Parse(_) => "failed to parse decimal",
+            Error::Arithmetic(_) => "decimal arithmetic failed",
+            Error::Wire(_) => "failed to encode or decode decimal",
+            Error::Stats(_) => "failed to compute decimal statistic",
+            Error::ParseExpr(_) => "failed to parse decimal expression",
+            Error::Eval(_) => "failed to evaluate decimal expression",
+            Error::Exchange(_) => "currency exchange failed",
+            Error::Ledger(_) => "ledger rejected change",
+            Error::ParsePercent(_) => "failed to parse percentage",
+            Error::ParseQuantity(_) => "failed to parse quantity",
+        })
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            Error::Parse(ref e) => Some(e),
+            Error::Arithmetic(ref e) => Some(e),
+            Error::Wire(ref e) => Some(e),
+            Error::Stats(ref e) => Some(e),
+            Error::ParseExpr(ref e) => Some(e),
+            Error::Eval(ref e) => Some(e),
+            Error::Exchange(ref e) => Some(e),
+            Error::Ledger(ref e) => Some(e),
+            Error::ParsePercent(ref e) => Some(e),
+            Error::ParseQuantity(ref e) => Some(e),
+        }
+    }
+}
+
+impl From<ParseDecimalError> for Error {
+    fn from(e: ParseDecimalError) -> Error {
+        Error::Parse(e)
+    }
+}
+
+impl From<ArithmeticError> for Error {
+    fn from(e: ArithmeticError) -> Error {
+        Error::Arithmetic(e)
+    }
+}
+
+impl From<wire::WireError> for Error {
+    fn from(e: wire::WireError) -> Error {
+        Error::Wire(e)
+    }
+}
+
+impl From<stats::StatsError> for Error {
+    fn from(e: stats::StatsError) -> Error {
+        Error::Stats(e)
+    }
+}
+
+impl From<expr::ParseExprError> for Error {
+    fn from(e: expr::ParseExprError) -> Error {
+        Error::ParseExpr(e)
+    }
+}
+
+impl From<expr::EvalError> for Error {
+    fn from(e: expr::EvalError) -> Error {
+        Error::Eval(e)
+    }
+}
+
+impl From<exchange::ExchangeError> for Error {
+    fn from(e: exchange::ExchangeError) -> Error {
+        Error::Exchange(e)
+    }
+}
+
+impl From<ledger::LedgerError> for Error {
+    fn from(e: ledger::LedgerError) -> Error {
+        Error::Ledger(e)
+    }
+}
+
+impl From<percent::ParsePercentError> for Error {
+    fn from(e: percent::ParsePercentError) -> Error {
+        Error::ParsePercent(e)
+    }
+}
+
+impl From<quantity::ParseQuantityError> for Error {
+    fn from(e: quantity::ParseQuantityError) -> Error {
+        Error::ParseQuantity(e)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Decimal;
@@ -313,9 +1150,89 @@ mod tests {
         assert!(Decimal::new(1, 0) != Decimal::new(2, 0));
     }
     #[test]
-    #[should_panic(expected = "arithmetic operation overflowed")]
+    #[should_panic(expected = "attempt to multiply with overflow")]
     fn adjust_scale_to_overflow_unscaled_value_panics() {
-        Decimal::new(::std::i64::MAX, 3).adjust_scale(4);
+        Decimal::new(i64::MAX, 3).adjust_scale(4);
+    }
+    #[test]
+    fn rounding_modes() {
+        use super::RoundingMode::*;
+        let cases = [
+            // value, Down, Up, Floor, Ceiling, HalfUp, HalfDown, HalfEven
+            (55, [5, 6, 5, 6, 6, 5, 6]),
+            (25, [2, 3, 2, 3, 3, 2, 2]),
+            (16, [1, 2, 1, 2, 2, 2, 2]),
+            (11, [1, 2, 1, 2, 1, 1, 1]),
+            (10, [1, 1, 1, 1, 1, 1, 1]),
+            (-11, [-1, -2, -2, -1, -1, -1, -1]),
+            (-16, [-1, -2, -2, -1, -2, -2, -2]),
+            (-25, [-2, -3, -3, -2, -3, -2, -2]),
+        ];
+        for &(unscaled, expected) in cases.iter() {
+            for (&mode, &rounded) in [Down, Up, Floor, Ceiling, HalfUp, HalfDown, HalfEven]
+                    .iter().zip(expected.iter()) {
+                assert_eq!(Decimal::new(rounded, 0), Decimal::new(unscaled, 1).round(0, mode),
+                           "{} rounded {:?}", Decimal::new(unscaled, 1), mode);
+            }
+        }
+    }
+    #[test]
+    fn rounding_away_every_place() {
+        use super::RoundingMode::*;
+        assert_eq!(Decimal::new(0, 0), Decimal::new(5, 40).round(0, HalfUp));
+        assert_eq!(Decimal::new(1, 0), Decimal::new(5, 40).round(0, Up));
+        assert_eq!(Decimal::new(-1, 0), Decimal::new(-5, 400).round(0, Floor));
+        assert_eq!(Decimal::new(0, 0), Decimal::new(-5, 400).round(0, Ceiling));
+    }
+    #[test]
+    fn precision_counts_unscaled_digits() {
+        assert_eq!(1, Decimal::new(0, 0).precision());
+        assert_eq!(1, Decimal::new(-7, 3).precision());
+        assert_eq!(4, Decimal::new(1000, 0).precision());
+        assert_eq!(19, Decimal::new(i64::MAX, 0).precision());
+        assert_eq!(19, Decimal::new(i64::MIN, 5).precision());
+    }
+    #[test]
+    fn rounding_to_significant_digits() {
+        use super::RoundingMode::*;
+        assert_eq!(Decimal::new(123, -2), Decimal::new(12345, 0).round_to_significant(3, HalfEven));
+        assert_eq!(Decimal::new(-124, 2), Decimal::new(-12351, 4).round_to_significant(3, HalfEven));
+        assert_eq!(Decimal::new(-123, 2), Decimal::new(-12351, 4).round_to_significant(3, Ceiling));
+        assert_eq!(Decimal::new(1, 3), Decimal::new(1, 3).round_to_significant(1, Up));
+        assert_eq!(Decimal::new(1, 1), Decimal::new(95, 3).round_to_significant(1, HalfUp));
+        assert_eq!(Decimal::new(-1, -3), Decimal::new(-999, 0).round_to_significant(1, Floor));
+        assert_eq!(Decimal::new(922, -16), Decimal::new(i64::MAX, 0).round_to_significant(3, HalfUp));
+        assert_eq!(Decimal::new(1, -19), Decimal::new(i64::MAX, 0).round_to_significant(1, Up));
+    }
+    #[test]
+    #[should_panic(expected = "zero significant digits")]
+    fn rounding_to_no_significant_digits_panics() {
+        Decimal::new(1, 0).round_to_significant(0, super::RoundingMode::HalfUp);
+    }
+    #[test]
+    fn formatting_significant_digits() {
+        use super::RoundingMode::*;
+        assert_eq!("9.81", Decimal::new(980665, 5).to_significant(3, HalfEven));
+        assert_eq!("10.0", Decimal::new(9996, 3).to_significant(3, HalfUp));
+        assert_eq!("-0.0500", Decimal::new(-5, 2).to_significant(3, HalfUp));
+        assert_eq!("0.00", Decimal::new(0, 0).to_significant(3, HalfUp));
+        assert_eq!("1.0E+3", Decimal::new(1, -3).to_significant(2, HalfUp));
+        assert_eq!("1E+3", Decimal::new(999, 0).to_significant(1, HalfUp));
+        assert_eq!("12.3456000000000000000000", Decimal::new(123456, 4).to_significant(24, HalfUp));
+    }
+    #[test]
+    fn fitting_sql_decimal_columns() {
+        assert!(Decimal::new(0, 9).fits_sql_decimal(1, 0));
+        assert!(Decimal::new(-99999, 2).fits_sql_decimal(5, 2));
+        assert!(Decimal::new(5, 1).fits_sql_decimal(1, 1));
+        assert!(!Decimal::new(15, 1).fits_sql_decimal(1, 1));
+        assert!(Decimal::new(12, -3).fits_sql_decimal(5, 0));
+        assert!(!Decimal::new(12, -3).fits_sql_decimal(5, 1));
+        assert!(Decimal::new(12, -3).fits_sql_decimal(6, 1));
+        assert!(Decimal::new(1230000, 6).fits_sql_decimal(3, 2));
+        assert!(!Decimal::new(1, 0).fits_sql_decimal(2, 3));
+        assert!(Decimal::new(i64::MIN, 0).fits_sql_decimal(19, 0));
+        assert!(!Decimal::new(i64::MIN, 0).fits_sql_decimal(38, 20));
     }
     #[test]
     fn parse_from_str() {
@@ -329,19 +1246,87 @@ mod tests {
         assert_eq!(Ok(Decimal::new(-125, 2)), "-1.25".parse());
         assert_eq!(Ok(Decimal::new(0, 0)), "000".parse());
         assert_eq!(Ok(Decimal::new(0, 0)), "0.".parse());
-        assert_eq!(Ok(Decimal::new(0, 0)), "-".parse()); // error?
-        assert_eq!(Ok(Decimal::new(0, 0)), ".".parse()); // error?
-        assert_eq!(Ok(Decimal::new(0, 0)), "-.".parse()); // error?
+        assert_eq!(Ok(Decimal::new(i64::MIN, 2)), "-92233720368547758.08".parse());
     }
     #[test]
     fn parse_failures() {
-        use std::error::Error;
+        use super::DecimalErrorKind::{self, *};
+        fn kind(s: &str) -> DecimalErrorKind {
+            s.parse::<Decimal>().err().unwrap().kind().clone()
+        }
+        fn invalid(character: char, index: usize) -> DecimalErrorKind {
+            InvalidChar { character, byte_index: index, char_index: index }
+        }
+        assert_eq!(invalid('g', 1), kind("2g"));
+        assert_eq!(invalid('-', 1), kind("2-2"));
+        assert_eq!(Empty, kind(""));
+        assert_eq!(invalid('E', 1), kind("1E"));
+        assert_eq!(invalid('+', 4), kind("1e+3+"));
+        assert_eq!(invalid('é', 3), kind("1.5é"));
+        assert_eq!(ExponentOutOfRange, kind("1e-2147483648"));
+        assert_eq!(NoDigits, kind("-"));
+        assert_eq!(NoDigits, kind("."));
+        assert_eq!(NoDigits, kind("-."));
+        assert_eq!(NoDigits, kind("-e5"));
+        assert_eq!(invalid('.', 3), kind("1.2.3"));
+        assert_eq!(Overflow, kind("9223372036854775808"));
+        assert_eq!(Overflow, kind("99999999999999999999"));
+    }
+    #[test]
+    fn parse_failure_messages() {
         assert_eq!("invalid character 'g' found at index 1",
-                   "2g".parse::<Decimal>().err().unwrap().description());
-        assert_eq!("invalid character '-' found at index 1",
-                   "2-2".parse::<Decimal>().err().unwrap().description());
+                   "2g".parse::<Decimal>().err().unwrap().to_string());
         assert_eq!("cannot parse decimal from empty string",
-                   "".parse::<Decimal>().err().unwrap().description());
+                   "".parse::<Decimal>().err().unwrap().to_string());
+        assert_eq!("exponent out of range for decimal",
+                   "1e-2147483648".parse::<Decimal>().err().unwrap().to_string());
+    }
+    #[test]
+    fn parse_failures_locate_the_bad_character() {
+        let err = "1.5é".parse::<Decimal>().err().unwrap();
+        assert_eq!(Some(3..5), err.span());
+        assert_eq!("1.5é\n   ^ invalid character 'é' found at index 3", err.annotate("1.5é"));
+        let err = "".parse::<Decimal>().err().unwrap();
+        assert_eq!(None, err.span());
+        assert_eq!("\ncannot parse decimal from empty string", err.annotate(""));
+    }
+    #[test]
+    fn parse_with_exponent() {
+        assert_eq!(Ok(Decimal::new(12, -19)), "1.2E+20".parse());
+        assert_eq!(Ok(Decimal::new(12, -19)), "12e19".parse());
+        assert_eq!(Ok(Decimal::new(-5, -3)), "-5E3".parse());
+        assert_eq!(Ok(Decimal::new(15, 4)), "1.5e-3".parse());
+        assert_eq!(Ok(Decimal::new(150, 0)), "1.50E2".parse());
+        assert_eq!(Ok(Decimal::new(1, i32::MIN)), "1e2147483648".parse());
+    }
+    #[test]
+    fn negative_scales_coarsen_past_integers() {
+        assert_eq!(Decimal::new(12, -1), Decimal::new(125, 0).adjust_scale(-1));
+        assert_eq!(Decimal::new(1200, 0), Decimal::new(12, -2).adjust_scale(0));
+        assert!(Decimal::new(12, -2) > Decimal::new(1199, 0));
+        assert!(Decimal::new(12, -2) < Decimal::new(12001, 1));
+        assert_eq!(Decimal::new(1205, 0), Decimal::new(12, -2) + Decimal::new(5, 0));
+        assert_eq!(Decimal::new(36, -20), Decimal::new(12, -19) * Decimal::new(3, -1));
+        assert_eq!(Decimal::new(4, 0), Decimal::new(12, -19) / Decimal::new(3, -19));
+        assert_eq!(Decimal::new(433, 0), Decimal::new(13, -2) / Decimal::new(3, 0));
+        assert_eq!(Decimal::new(1, -2), Decimal::new(13, -2) % Decimal::new(3, -2));
+    }
+    #[test]
+    fn comparing_across_wide_scale_gaps_does_not_overflow() {
+        use std::cmp::Ordering::*;
+        let cmp = |a: Decimal, b: Decimal| a.partial_cmp(&b).unwrap();
+        assert_eq!(Greater, cmp(Decimal::new(12, -19), Decimal::new(1, 0)));
+        assert_eq!(Less, cmp(Decimal::new(1, 0), Decimal::new(12, -19)));
+        assert_eq!(Less, cmp(Decimal::new(-12, -19), Decimal::new(-1, 0)));
+        assert_eq!(Greater, cmp(Decimal::new(-1, 0), Decimal::new(-12, -19)));
+        assert_eq!(Less, cmp(Decimal::new(1, 40), Decimal::new(i64::MAX, 0)));
+        assert_eq!(Greater, cmp(Decimal::new(i64::MIN, 0), Decimal::new(i64::MIN, -19)));
+        assert_eq!(Greater, cmp(Decimal::new(1, i32::MIN), Decimal::new(1, i32::MAX)));
+        assert_eq!(Less, cmp(Decimal::new(-1, i32::MIN), Decimal::new(1, i32::MAX)));
+        assert_eq!(Equal, cmp(Decimal::new(0, i32::MIN), Decimal::new(0, i32::MAX)));
+        assert_eq!(Equal, cmp(Decimal::new(1_000_000_000_000_000_000, 18), Decimal::new(1, 0)));
+        assert_eq!(Less, cmp(Decimal::new(999_999_999_999_999_999, 18), Decimal::new(1, 0)));
+        assert_eq!(Greater, cmp(Decimal::new(-999_999_999_999_999_999, 18), Decimal::new(-1, 0)));
     }
     #[test]
     fn adding_decimals_with_same_scale_maintains_scale() {
@@ -352,18 +1337,18 @@ mod tests {
         assert_eq!(Decimal::new(100, 2), Decimal::new(9, 1) + Decimal::new(10, 2));
     }
     #[test]
-    #[should_panic(expected = "arithmetic operation overflowed")]
+    #[should_panic(expected = "attempt to add with overflow")]
     fn adding_to_overflow_unscaled_value_panics() {
-        Decimal::new(::std::i64::MAX, 0) + Decimal::new(1, 0);
+        let _ = Decimal::new(i64::MAX, 0) + Decimal::new(1, 0);
     }
     #[test]
     fn subtracting_decimals_with_different_scales_results_in_larger_scale() {
         assert_eq!(Decimal::new(100, 2), Decimal::new(11, 1) - Decimal::new(10, 2));
     }
     #[test]
-    #[should_panic(expected = "arithmetic operation overflowed")]
+    #[should_panic(expected = "attempt to subtract with overflow")]
     fn subtracting_to_overflow_unscaled_value_panics() {
-        Decimal::new(::std::i64::MIN, 0) - Decimal::new(1, 0);
+        let _ = Decimal::new(i64::MIN, 0) - Decimal::new(1, 0);
     }
     #[test]
     fn multiplying_decimals_results_in_summed_scales() {
@@ -377,14 +1362,14 @@ mod tests {
         assert_eq!(Decimal::new(246, 2), 2 * Decimal::new(123, 2));
     }
     #[test]
-    #[should_panic(expected = "arithmetic operation overflowed")]
+    #[should_panic(expected = "attempt to multiply with overflow")]
     fn multiplying_to_overflow_unscaled_value_panics() {
-        Decimal::new(::std::i64::MAX, 2) * Decimal::new(10001, 4);
+        let _ = Decimal::new(i64::MAX, 2) * Decimal::new(10001, 4);
     }
     #[test]
-    #[should_panic(expected = "arithmetic operation overflowed")]
+    #[should_panic(expected = "attempt to add with overflow")]
     fn multiplying_to_overflow_scale_panics() {
-        Decimal::new(1, ::std::u32::MAX) * Decimal::new(1, 1);
+        let _ = Decimal::new(1, i32::MAX) * Decimal::new(1, 1);
     }
     #[test]
     fn dividing_decimal_by_decimal() {
@@ -397,11 +1382,131 @@ mod tests {
         assert_eq!(Decimal::new(212, 2), Decimal::new(425, 2) / Decimal::new(2, 0));
     }
     #[test]
+    fn multiplies_with_rounding() {
+        use super::RoundingMode::*;
+        assert_eq!(Decimal::new(-165, 2), Decimal::new(-1999, 2).mul_round(Decimal::new(825, 4), 2, HalfEven));
+        assert_eq!(Decimal::new(8507059173023461585, -19),
+                   Decimal::new(i64::MAX, 0).mul_round(Decimal::new(i64::MAX, 0), -19, HalfUp));
+        assert_eq!(Decimal::new(600, 2), Decimal::new(2, 0).mul_round(Decimal::new(3, 0), 2, Down));
+        assert_eq!(Decimal::new(0, 0), Decimal::new(1, 30).mul_round(Decimal::new(1, 30), 0, HalfUp));
+        assert_eq!(Decimal::new(1, 0), Decimal::new(1, i32::MAX).mul_round(Decimal::new(1, i32::MAX), 0, Up));
+    }
+    #[test]
+    fn quantizes_to_increments() {
+        use super::RoundingMode::*;
+        let tick = Decimal::new(25, 2);
+        let price = Decimal::new(10125, 3);
+        assert_eq!(Decimal::new(1000, 2), price.quantize_to_increment(tick, Down));
+        assert_eq!(Decimal::new(1025, 2), price.quantize_to_increment(tick, Up));
+        assert_eq!(Decimal::new(1000, 2), price.quantize_to_increment(tick, HalfEven));
+        assert_eq!(Decimal::new(1025, 2), price.quantize_to_increment(tick, HalfUp));
+        assert_eq!(Decimal::new(-1025, 2), Decimal::new(-10125, 3).quantize_to_increment(tick, Floor));
+        assert_eq!(Decimal::new(-1000, 2),
+                   Decimal::new(-10125, 3).quantize_to_increment(Decimal::new(-25, 2), Ceiling));
+        assert_eq!(Decimal::new(0, 2), Decimal::new(0, 7).quantize_to_increment(tick, Up));
+        assert_eq!(Decimal::new(45, 1), Decimal::new(31, 1).quantize_to_increment(Decimal::new(15, 1), Up));
+        assert_eq!(Decimal::new(1, 9), Decimal::new(1, 30).quantize_to_increment(Decimal::new(1, 9), Up));
+        assert_eq!(Decimal::new(0, -30), Decimal::new(1, 0).quantize_to_increment(Decimal::new(1, -30), HalfUp));
+        assert_eq!(Decimal::new(1, -30), Decimal::new(1, 0).quantize_to_increment(Decimal::new(1, -30), Up));
+    }
+    #[test]
+    #[should_panic(expected = "increment must not be zero")]
+    fn quantizing_to_zero_increment_panics() {
+        let _ = Decimal::new(1, 0).quantize_to_increment(Decimal::new(0, 2), super::RoundingMode::Down);
+    }
+    #[test]
+    fn checks_multiples_of_increments() {
+        let lot = Decimal::new(5, -2);
+        assert!(Decimal::new(1500, 0).is_multiple_of(lot));
+        assert!(Decimal::new(-15, -2).is_multiple_of(lot));
+        assert!(!Decimal::new(1550, 0).is_multiple_of(lot));
+        assert!(!Decimal::new(1, 0).is_multiple_of(Decimal::new(1, -30)));
+        assert!(Decimal::new(3, 0).is_multiple_of(Decimal::new(-3, 40)));
+        assert!(Decimal::new(1, 0).is_multiple_of(Decimal::new(8, 4000)));
+        assert!(!Decimal::new(1, 0).is_multiple_of(Decimal::new(3, 4000)));
+        assert!(Decimal::new(0, 0).is_multiple_of(Decimal::new(0, 0)));
+        assert!(!Decimal::new(1, 0).is_multiple_of(Decimal::new(0, 0)));
+    }
+    #[test]
+    fn checked_addition_subtraction_and_multiplication_report_errors() {
+        use super::ArithmeticError::*;
+        assert_eq!(Ok(Decimal::new(1205, 0)), Decimal::new(12, -2).try_add(Decimal::new(5, 0)));
+        assert_eq!(Ok(Decimal::new(-1, 3)), Decimal::new(0, 0).try_sub(Decimal::new(1, 3)));
+        assert_eq!(Ok(Decimal::new(36, -20)), Decimal::new(12, -19).try_mul(Decimal::new(3, -1)));
+        assert_eq!(Err(Overflow), Decimal::new(i64::MIN, 0).try_sub(Decimal::new(1, 0)));
+        assert_eq!(Err(Overflow), Decimal::new(i64::MAX, 0).try_add(Decimal::new(0, 1)));
+        assert_eq!(Ok(Decimal::new(i64::MAX, 0)),
+                   Decimal::new(i64::MAX, 0).try_add(Decimal::new(0, -30)));
+        assert_eq!(Err(Overflow), Decimal::new(i64::MAX, 0).try_mul(Decimal::new(2, 0)));
+        assert_eq!(Err(ScaleOverflow), Decimal::new(1, i32::MAX).try_mul(Decimal::new(1, 1)));
+    }
+    #[test]
+    fn divides_with_rounding() {
+        use super::ArithmeticError::*;
+        use super::RoundingMode::*;
+        assert_eq!(Ok(Decimal::new(28, 0)), Decimal::new(700, 2).div_round(Decimal::new(25, 2), 0, Down));
+        assert_eq!(Ok(Decimal::new(-67, 2)), Decimal::new(2, 0).div_round(Decimal::new(-3, 0), 2, HalfUp));
+        assert_eq!(Ok(Decimal::new(-66, 2)), Decimal::new(2, 0).div_round(Decimal::new(-3, 0), 2, Down));
+        assert_eq!(Ok(Decimal::new(4, -2)), Decimal::new(1000, 0).div_round(Decimal::new(3, 0), -2, Up));
+        assert_eq!(Ok(Decimal::new(0, 0)), Decimal::new(1, 0).div_round(Decimal::new(1, -40), 0, HalfUp));
+        assert_eq!(Ok(Decimal::new(-1, 0)), Decimal::new(1, 0).div_round(Decimal::new(-1, -40), 0, Up));
+        assert_eq!(Err(Overflow), Decimal::new(1, 0).div_round(Decimal::new(1, 0), 40, Down));
+        assert_eq!(Err(Overflow), Decimal::new(i64::MAX, 0).div_round(Decimal::new(1, 1), 0, Down));
+        assert_eq!(Err(DivisionByZero), Decimal::new(1, 0).div_round(Decimal::new(0, 5), 0, Down));
+    }
+    #[test]
+    fn checked_division_reports_errors() {
+        use super::ArithmeticError::*;
+        assert_eq!(Ok(Decimal::new(212, 2)), Decimal::new(425, 2).try_div(Decimal::new(2, 0)));
+        assert_eq!(Ok(Decimal::new(30, 0)), Decimal::new(75, 1).try_div(Decimal::new(25, 2)));
+        assert_eq!(Err(DivisionByZero), Decimal::new(1, 0).try_div(Decimal::new(0, 0)));
+        assert_eq!(Err(DivisionByZero), Decimal::new(0, 0).try_rem(Decimal::new(0, 3)));
+        assert_eq!(Err(Overflow), Decimal::new(i64::MIN, 0).try_div(Decimal::new(-1, 0)));
+        assert_eq!(Ok(Decimal::new(0, 0)), Decimal::new(i64::MIN, 0).try_rem(Decimal::new(-1, 0)));
+        assert_eq!(Err(Overflow), Decimal::new(i64::MAX, 0).try_div(Decimal::new(1, 1)));
+        assert_eq!(Err(ScaleOverflow), Decimal::new(1, i32::MAX).try_div(Decimal::new(1, -1)));
+        assert_eq!(Ok(Decimal::new(0, 40)), Decimal::new(0, 0).try_rem(Decimal::new(3, 40)));
+    }
+    #[test]
+    fn crate_error_wraps_specific_errors() {
+        use std::error::Error as StdError;
+        use super::{ArithmeticError, Error};
+        let parse_error: Error = "1x".parse::<Decimal>().unwrap_err().into();
+        assert_eq!("failed to parse decimal", parse_error.to_string());
+        assert_eq!("invalid character 'x' found at index 1",
+                   parse_error.source().unwrap().to_string());
+        let arithmetic_error = Error::from(ArithmeticError::DivisionByZero);
+        assert_eq!("decimal division by zero", arithmetic_error.source().unwrap().to_string());
+    }
+    #[test]
     fn get_remainder() {
         assert_eq!(Decimal::new(1, 0), Decimal::new(5, 0) % Decimal::new(2, 0));
         assert_eq!(Decimal::new(1, 2), Decimal::new(425, 2) % Decimal::new(2, 0));
     }
     #[test]
+    fn division_modes_differ_only_for_negative_operands() {
+        let pairs = [(Decimal::new(75, 1), Decimal::new(2, 0)),
+                     (Decimal::new(-75, 1), Decimal::new(2, 0)),
+                     (Decimal::new(75, 1), Decimal::new(-2, 0)),
+                     (Decimal::new(-75, 1), Decimal::new(-2, 0))];
+        let truncated: Vec<_> = pairs.iter().map(|&(a, b)| a.div_rem(b)).collect();
+        assert_eq!(vec![(Decimal::new(37, 1), Decimal::new(1, 1)),
+                        (Decimal::new(-37, 1), Decimal::new(-1, 1)),
+                        (Decimal::new(-37, 1), Decimal::new(1, 1)),
+                        (Decimal::new(37, 1), Decimal::new(-1, 1))],
+                   truncated);
+        let euclid: Vec<_> = pairs.iter().map(|&(a, b)| (a.div_euclid(b), a.rem_euclid(b))).collect();
+        assert_eq!(vec![(Decimal::new(37, 1), Decimal::new(1, 1)),
+                        (Decimal::new(-38, 1), Decimal::new(1, 1)),
+                        (Decimal::new(-37, 1), Decimal::new(1, 1)),
+                        (Decimal::new(38, 1), Decimal::new(1, 1))],
+                   euclid);
+        let floor: Vec<_> = pairs.iter().map(|&(a, b)| a.div_floor(b)).collect();
+        assert_eq!(vec![Decimal::new(37, 1), Decimal::new(-38, 1),
+                        Decimal::new(-38, 1), Decimal::new(37, 1)],
+                   floor);
+    }
+    #[test]
     fn ops_on_negative_decimals() {
         assert_eq!(Decimal::new(10, 1), Decimal::new(12, 1) + Decimal::new(-2, 1));
         assert_eq!(Decimal::new(-1, 3), Decimal::new(0, 0) - Decimal::new(1, 3));
@@ -427,6 +1532,13 @@ mod tests {
         assert_eq!("-0.1", format!("{}", Decimal::new(-1, 1)));
     }
     #[test]
+    fn displays_negative_scales_in_scientific_notation() {
+        assert_eq!("1.2E+20", format!("{}", Decimal::new(12, -19)));
+        assert_eq!("-1.20E+21", format!("{}", Decimal::new(-120, -19)));
+        assert_eq!("5E+1", format!("{}", Decimal::new(5, -1)));
+        assert_eq!("0E+3", format!("{}", Decimal::new(0, -3)));
+    }
+    #[test]
     fn supports_debug_format() {
         assert_eq!("Decimal { unscaled: 1, scale: 2 }",
                    format!("{:?}", Decimal::new(1, 2)));
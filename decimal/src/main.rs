@@ -0,0 +1,159 @@
+//! A calculator for checking `Decimal` arithmetic by hand.
+//!
+//! Evaluates expressions like `3.50 * 2 / 0.25`, either given as arguments or read from
+//! stdin a line at a time, and prints each result. See `USAGE` for the options and
+//! `decimal::expr` for the expressions.
+
+extern crate decimal;
+
+use std::collections::HashMap;
+use std::env;
+use std::io::{self, BufRead};
+use std::process;
+
+use decimal::RoundingMode;
+use decimal::expr::{Context, Expr};
+
+const USAGE: &str = "\
+usage: decimal [--scale N] [--rounding MODE] [EXPRESSION...]
+
+Evaluates each EXPRESSION (the arguments, joined with spaces) or, if there are none,
+each line of stdin. Expressions use + - * / % and parentheses with the usual precedence.
+
+options:
+    --scale N         divide to N places and round each result to N places
+                      (without it, / truncates like Decimal's / operator)
+    --rounding MODE   how to round at the scale: down, up, floor, ceiling, half-up,
+                      half-down or half-even (the default)
+    -h, --help        print this message";
+
+fn rounding_mode(name: &str) -> Option<RoundingMode> {
+    Some(match name {
+        "down" => RoundingMode::Down,
+        "up" => RoundingMode::Up,
+        "floor" => RoundingMode::Floor,
+        "ceiling" => RoundingMode::Ceiling,
+        "half-up" => RoundingMode::HalfUp,
+        "half-down" => RoundingMode::HalfDown,
+        "half-even" => RoundingMode::HalfEven,
+        _ => return None,
+    })
+}
+
+/// Splits the command line into options and the words of the expression, if any.
+/// `Ok(None)` means help was asked for.
+fn parse_args<I: Iterator<Item = String>>(mut args: I)
+                                          -> Result<Option<(Context, Vec<String>)>, String> {
+    let mut context = Context::default();
+    let mut words = vec![];
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(None);
+        }
+        if arg == "--" {
+            words.extend(args.by_ref());
+            break;
+        }
+        if !arg.starts_with("--") {
+            words.push(arg);
+            continue;
+        }
+        let (name, value) = match arg.find('=') {
+            Some(i) => (arg[..i].to_string(), arg[i + 1..].to_string()),
+            None => {
+                let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                (arg, value)
+            }
+        };
+        match &name[..] {
+            "--scale" => {
+                let scale = value.parse().map_err(|_| format!("invalid scale '{}'", value))?;
+                context.scale = Some(scale);
+            }
+            "--rounding" => {
+                let mode = rounding_mode(&value)
+                    .ok_or_else(|| format!("unknown rounding mode '{}'", value))?;
+                context.rounding = mode;
+            }
+            _ => return Err(format!("unknown option '{}'", name)),
+        }
+    }
+    Ok(Some((context, words)))
+}
+
+/// Prints the value of `input` or the error, returning whether it evaluated.
+fn run(input: &str, context: Context) -> bool {
+    let expr = match input.parse::<Expr>() {
+        Ok(expr) => expr,
+        Err(e) => {
+            eprintln!("{}", e.annotate(input));
+            return false;
+        }
+    };
+    match expr.evaluate(&HashMap::new(), context) {
+        Ok(value) => {
+            println!("{}", value);
+            true
+        }
+        Err(e) => {
+            eprintln!("{}\n{}", input, e);
+            false
+        }
+    }
+}
+
+fn main() {
+    let (context, words) = match parse_args(env::args().skip(1)) {
+        Ok(Some(parsed)) => parsed,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(message) => {
+            eprintln!("decimal: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+    let ok = if words.is_empty() {
+        let stdin = io::stdin();
+        let mut ok = true;
+        for line in stdin.lock().lines() {
+            let line = line.unwrap_or_else(|e| {
+                eprintln!("decimal: error reading stdin: {}", e);
+                process::exit(2);
+            });
+            if !line.trim().is_empty() {
+                ok &= run(&line, context);
+            }
+        }
+        ok
+    } else {
+        run(&words.join(" "), context)
+    };
+    if !ok {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_args;
+    use decimal::expr::Context;
+
+    #[test]
+    fn parses_options() {
+        use decimal::RoundingMode::*;
+        let args = |a: &[&str]| parse_args(a.iter().map(|s| s.to_string()));
+        let words = |w: &[&str]| w.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(Ok(Some((Context::new(2, Up), words(&["1", "/", "3"])))),
+                   args(&["--scale", "2", "1", "/", "--rounding=up", "3"]));
+        assert_eq!(Ok(Some((Context::default(), words(&["-1", "--scale"])))),
+                   args(&["-1", "--", "--scale"]));
+        assert_eq!(Ok(None), args(&["1", "--help"]));
+        assert_eq!(Err("--scale needs a value".to_string()), args(&["--scale"]));
+        assert_eq!(Err("invalid scale 'x'".to_string()), args(&["--scale=x"]));
+        assert_eq!(Err("unknown rounding mode 'nearest'".to_string()),
+                   args(&["--rounding", "nearest"]));
+        assert_eq!(Err("unknown option '--precision'".to_string()), args(&["--precision=2"]));
+    }
+}
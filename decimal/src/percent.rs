@@ -0,0 +1,320 @@
+//! Percentages and basis points.
+//!
+//! `Percent` and `BasisPoints` hold the number as written (`7.25` for 7.25%, `15` for
+//! 15bp), so there's no guessing whether a `Decimal` is already divided by 100. Applying
+//! one to an amount rounds the result to the amount's scale with the caller's choice of
+//! `RoundingMode`.
+//!
+//! A discount is a negative change, so successive discounts compound like any other
+//! changes.
+//!
+//! # Examples
+//! ```
+//! # use decimal::{Decimal, RoundingMode};
+//! # use decimal::percent::{BasisPoints, Percent};
+//! let tax: Percent = "7.25%".parse().unwrap();
+//! let price = Decimal::new(1999, 2);
+//! assert_eq!(Decimal::new(145, 2), tax.of(price, RoundingMode::HalfUp));
+//! assert_eq!(Decimal::new(2144, 2), tax.apply(price, RoundingMode::HalfUp));
+//!
+//! let sale = Percent::new(Decimal::new(-10, 0)).compound("-5%".parse().unwrap());
+//! assert_eq!("-14.50%", sale.to_string());
+//!
+//! let fee: BasisPoints = "15bp".parse().unwrap();
+//! assert_eq!(Percent::new(Decimal::new(15, 2)), Percent::from(fee));
+//! ```
+
+use std::error;
+use std::fmt;
+use std::str;
+
+use {ArithmeticError, Decimal, ParseDecimalError, RoundingMode};
+
+/// A percentage, such as a tax rate or discount.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Percent(Decimal);
+
+/// A hundredth of a percent, as fees and interest rate moves are often quoted.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct BasisPoints(Decimal);
+
+impl Percent {
+    /// The percentage written as `value`%, e.g. `Percent::new(Decimal::new(725, 2))`
+    /// for 7.25%.
+    pub fn new(value: Decimal) -> Percent {
+        Percent(value)
+    }
+
+    /// The number of percent, e.g. `7.25` for 7.25%.
+    pub fn value(&self) -> Decimal {
+        self.0
+    }
+
+    /// The percentage as a plain fraction, e.g. `0.0725` for 7.25%. Fails with `ScaleOverflow` if
+    /// the fraction's scale doesn't fit in an `i32`.
+    pub fn as_fraction(&self) -> Result<Decimal, ArithmeticError> {
+        move_point(self.0, 2)
+    }
+
+    /// This percentage of `amount`, rounded to `amount`'s scale.
+    ///
+    /// Panics if the result overflows, as `mul_round` does, or if `as_fraction` fails.
+    pub fn of(&self, amount: Decimal, mode: RoundingMode) -> Decimal {
+        let fraction = self.as_fraction().expect("attempt to apply with scale overflow");
+        amount.mul_round(fraction, amount.scale, mode)
+    }
+
+    /// `amount` changed by this percentage, e.g. with tax added or (for a negative
+    /// percentage) a discount taken off, rounded to `amount`'s scale.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{Decimal, RoundingMode};
+    /// # use decimal::percent::Percent;
+    /// let discount: Percent = "-15%".parse().unwrap();
+    /// assert_eq!(Decimal::new(4249, 2), discount.apply(Decimal::new(4999, 2), RoundingMode::HalfEven));
+    /// ```
+    pub fn apply(&self, amount: Decimal, mode: RoundingMode) -> Decimal {
+        amount + self.of(amount, mode)
+    }
+
+    /// The single change equivalent to this one followed by `next`, computed exactly.
+    /// Applying the result matches applying both in turn, up to rounding.
+    pub fn compound(&self, next: Percent) -> Percent {
+        Percent(compound(self.0, next.0, 2))
+    }
+}
+
+impl BasisPoints {
+    /// The rate written as `value`bp, e.g. `BasisPoints::new(Decimal::new(15, 0))`
+    /// for 15bp.
+    pub fn new(value: Decimal) -> BasisPoints {
+        BasisPoints(value)
+    }
+
+    /// The number of basis points, e.g. `15` for 15bp.
+    pub fn value(&self) -> Decimal {
+        self.0
+    }
+
+    /// The rate as a plain fraction, e.g. `0.0015` for 15bp. Fails with `ScaleOverflow` if
+    /// the fraction's scale doesn't fit in an `i32`.
+    pub fn as_fraction(&self) -> Result<Decimal, ArithmeticError> {
+        move_point(self.0, 4)
+    }
+
+    /// This many basis points of `amount`, rounded to `amount`'s scale. Panics in the
+    /// same cases as `Percent::of`.
+    pub fn of(&self, amount: Decimal, mode: RoundingMode) -> Decimal {
+        let fraction = self.as_fraction().expect("attempt to apply with scale overflow");
+        amount.mul_round(fraction, amount.scale, mode)
+    }
+
+    /// `amount` changed by this many basis points, rounded to `amount`'s scale.
+    pub fn apply(&self, amount: Decimal, mode: RoundingMode) -> Decimal {
+        amount + self.of(amount, mode)
+    }
+
+    /// The single change equivalent to this one followed by `next`, computed exactly.
+    pub fn compound(&self, next: BasisPoints) -> BasisPoints {
+        BasisPoints(compound(self.0, next.0, 4))
+    }
+}
+
+/// `a + b + a * b`, where `a` and `b` are fractions written with `places` extra places.
+fn compound(a: Decimal, b: Decimal, places: i32) -> Decimal {
+    let product = move_point(a * b, places).expect("attempt to compound with scale overflow");
+    a + b + product
+}
+
+/// Divides `value` by ten to the power `places`, which is exact: only the scale changes.
+fn move_point(value: Decimal, places: i32) -> Result<Decimal, ArithmeticError> {
+    let scale = value.scale.checked_add(places).ok_or(ArithmeticError::ScaleOverflow)?;
+    Ok(Decimal::new(value.unscaled, scale))
+}
+
+/// Moves the decimal point, without giving the result a negative scale, so `7%`
+/// becomes `700bp` rather than `7E+2bp`.
+fn shift_point(value: Decimal, places: i32) -> Decimal {
+    let shifted = move_point(value, places).expect("attempt to convert with scale overflow");
+    if shifted.scale < 0 { shifted.adjust_scale(0) } else { shifted }
+}
+
+impl From<BasisPoints> for Percent {
+    fn from(bp: BasisPoints) -> Percent {
+        Percent(shift_point(bp.0, 2))
+    }
+}
+
+impl From<Percent> for BasisPoints {
+    fn from(percent: Percent) -> BasisPoints {
+        BasisPoints(shift_point(percent.0, -2))
+    }
+}
+
+impl fmt::Display for Percent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}%", self.0)
+    }
+}
+
+impl fmt::Display for BasisPoints {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}bp", self.0)
+    }
+}
+
+/// Parses a decimal followed by `suffix`. If the suffix is missing, whatever the decimal
+/// parser makes of the whole string is reported, so errors point at the offending
+/// character.
+fn parse_with_suffix(s: &str, suffix: &'static str) -> Result<Decimal, ParsePercentError> {
+    match s.strip_suffix(suffix) {
+        Some(number) => Ok(number.parse()?),
+        None => {
+            s.parse::<Decimal>()?;
+            Err(ParsePercentError::MissingSuffix(suffix))
+        }
+    }
+}
+
+/// Parses strings like `7.25%`. The `%` is required.
+impl str::FromStr for Percent {
+    type Err = ParsePercentError;
+    fn from_str(s: &str) -> Result<Percent, ParsePercentError> {
+        parse_with_suffix(s, "%").map(Percent)
+    }
+}
+
+/// Parses strings like `15bp`. The `bp` is required.
+impl str::FromStr for BasisPoints {
+    type Err = ParsePercentError;
+    fn from_str(s: &str) -> Result<BasisPoints, ParsePercentError> {
+        parse_with_suffix(s, "bp").map(BasisPoints)
+    }
+}
+
+/// The error returned when parsing a `Percent` or `BasisPoints` fails.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParsePercentError {
+    /// The number isn't a valid decimal.
+    InvalidNumber(ParseDecimalError),
+    /// A valid decimal without the suffix (`%` or `bp`) the type requires.
+    MissingSuffix(&'static str),
+}
+
+impl fmt::Display for ParsePercentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParsePercentError::InvalidNumber(ref e) => e.fmt(f),
+            ParsePercentError::MissingSuffix(suffix) =>
+                write!(f, "decimal must be followed by '{}'", suffix),
+        }
+    }
+}
+
+impl error::Error for ParsePercentError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            ParsePercentError::InvalidNumber(ref e) => Some(e),
+            ParsePercentError::MissingSuffix(_) => None,
+        }
+    }
+}
+
+impl From<ParseDecimalError> for ParsePercentError {
+    fn from(e: ParseDecimalError) -> ParsePercentError {
+        ParsePercentError::InvalidNumber(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BasisPoints, ParsePercentError, Percent};
+    use {ArithmeticError, Decimal, DecimalErrorKind};
+    use RoundingMode::*;
+
+    fn percent(s: &str) -> Percent {
+        s.parse().unwrap()
+    }
+
+    fn bp(s: &str) -> BasisPoints {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_and_displays() {
+        assert_eq!(Percent::new(Decimal::new(725, 2)), percent("7.25%"));
+        assert_eq!(BasisPoints::new(Decimal::new(-15, 0)), bp("-15bp"));
+        assert_eq!("7.25%", percent("7.25%").to_string());
+        assert_eq!("0.5bp", bp("0.5bp").to_string());
+    }
+
+    #[test]
+    fn parse_failures() {
+        fn kind<T: ::std::str::FromStr<Err = ParsePercentError>>(s: &str) -> DecimalErrorKind {
+            match s.parse::<T>().err().unwrap() {
+                ParsePercentError::InvalidNumber(e) => e.kind().clone(),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        assert_eq!(Err(ParsePercentError::MissingSuffix("%")), "7.25".parse::<Percent>());
+        assert_eq!(Err(ParsePercentError::MissingSuffix("bp")), "15".parse::<BasisPoints>());
+        assert_eq!(DecimalErrorKind::InvalidChar { character: 'x', byte_index: 1, char_index: 1 },
+                   kind::<Percent>("7x%"));
+        assert_eq!(DecimalErrorKind::InvalidChar { character: 'b', byte_index: 2, char_index: 2 },
+                   kind::<Percent>("15bp"));
+        assert_eq!(DecimalErrorKind::Empty, kind::<Percent>("%"));
+        assert_eq!("decimal must be followed by 'bp'",
+                   "15".parse::<BasisPoints>().err().unwrap().to_string());
+    }
+
+    #[test]
+    fn converts_without_double_scaling() {
+        assert_eq!(Ok(Decimal::new(725, 4)), percent("7.25%").as_fraction());
+        assert_eq!(Ok(Decimal::new(15, 4)), bp("15bp").as_fraction());
+        assert_eq!(bp("725bp"), BasisPoints::from(percent("7.25%")));
+        assert_eq!(bp("700bp"), BasisPoints::from(percent("7%")));
+        assert_eq!(percent("0.15%"), Percent::from(bp("15bp")));
+        assert_eq!(percent("1%"), Percent::from(BasisPoints::new(Decimal::new(1, -2))));
+    }
+
+    #[test]
+    fn fraction_scale_overflow_is_an_error() {
+        assert_eq!(Err(ArithmeticError::ScaleOverflow),
+                   Percent::new(Decimal::new(1, i32::MAX)).as_fraction());
+        assert_eq!(Err(ArithmeticError::ScaleOverflow),
+                   BasisPoints::new(Decimal::new(1, i32::MAX - 3)).as_fraction());
+        assert_eq!(Ok(Decimal::new(1, i32::MAX)),
+                   BasisPoints::new(Decimal::new(1, i32::MAX - 4)).as_fraction());
+    }
+
+    #[test]
+    #[should_panic(expected = "scale overflow")]
+    fn taking_a_percentage_with_scale_overflow_panics() {
+        Percent::new(Decimal::new(1, i32::MAX)).of(Decimal::new(1, 0), HalfEven);
+    }
+
+    #[test]
+    fn applies_with_rounding() {
+        let amount = Decimal::new(1060, 2);
+        assert_eq!(Decimal::new(26, 2), percent("2.5%").of(amount, HalfEven));
+        assert_eq!(Decimal::new(27, 2), percent("2.5%").of(amount, HalfUp));
+        assert_eq!(Decimal::new(1034, 2), percent("-2.5%").apply(amount, HalfEven));
+        assert_eq!(Decimal::new(1033, 2), percent("-2.5%").apply(amount, Up));
+        assert_eq!(Decimal::new(2, 2), bp("15bp").of(amount, HalfUp));
+        assert_eq!(Decimal::new(1060, 2), bp("1bp").apply(amount, HalfEven));
+        assert_eq!(Decimal::new(1, -4), percent("10%").of(Decimal::new(1, -4), Up));
+    }
+
+    #[test]
+    fn compounds_successive_changes() {
+        assert_eq!("-14.50%", percent("-10%").compound(percent("-5%")).to_string());
+        assert_eq!("21.00%", percent("10%").compound(percent("10%")).to_string());
+        assert_eq!("0.00%", percent("25%").compound(percent("-20%")).to_string());
+        assert_eq!("30.0225bp", bp("15bp").compound(bp("15bp")).to_string());
+        let price = Decimal::new(20000, 2);
+        let total = percent("-10%").compound(percent("-5%"));
+        assert_eq!(percent("-5%").apply(percent("-10%").apply(price, HalfUp), HalfUp),
+                   total.apply(price, HalfUp));
+    }
+}
@@ -0,0 +1,370 @@
+//! Amounts of weight, volume and time, with the unit in the type.
+//!
+//! A `Quantity<Kilogram>` and a `Quantity<Pound>` are different types, so `+` and `-`
+//! only combine quantities in the same unit; anything else must be converted first,
+//! and only units measuring the same `Dimension` convert. Every unit is defined by an
+//! exact decimal factor to its dimension's base unit (a pound is 0.45359237 kg), so
+//! conversions are exact whenever the result has a finite decimal expansion. When it
+//! doesn't, `convert` fails with `ArithmeticError::Inexact` and `convert_round` rounds.
+//!
+//! # Examples
+//! ```
+//! # use decimal::{Decimal, RoundingMode};
+//! # use decimal::quantity::{Kilogram, Pound, Quantity};
+//! let parcel: Quantity<Kilogram> = "2.5 kg".parse().unwrap();
+//! let packing: Quantity<Pound> = "0.5 lb".parse().unwrap();
+//! let total = parcel + packing.convert::<Kilogram>().unwrap();
+//! assert_eq!("2.726796185 kg", total.to_string());
+//!
+//! let in_pounds = total.convert_round::<Pound>(2, RoundingMode::HalfUp).unwrap();
+//! assert_eq!(Decimal::new(601, 2), in_pounds.value());
+//! ```
+//!
+//! Quantities in different units can't be added:
+//!
+//! ```compile_fail
+//! # use decimal::quantity::{Kilogram, Liter, Quantity};
+//! let mass: Quantity<Kilogram> = "1 kg".parse().unwrap();
+//! let volume: Quantity<Liter> = "1 L".parse().unwrap();
+//! let nonsense = mass + volume;
+//! ```
+
+use std::cmp;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops;
+use std::str;
+
+use {divide_to_scale, gcd, ArithmeticError, Decimal, ParseDecimalError, RoundingMode};
+
+/// A unit of measure, as a type with no values used only to tag `Quantity`s.
+pub trait Unit {
+    /// What the unit measures. Only units of the same dimension convert to each other.
+    type Dimension;
+    /// How the unit is written, e.g. `kg`.
+    const SYMBOL: &'static str;
+    /// The size of one of this unit in the dimension's base unit, e.g. 0.001 for grams
+    /// (of kilograms).
+    fn factor() -> Decimal;
+}
+
+/// The dimension of `Kilogram`, `Gram`, `Pound` and `Ounce`.
+pub enum Mass {}
+/// The dimension of `Liter`, `Milliliter` and `Gallon`.
+pub enum Volume {}
+/// The dimension of `Second`, `Minute` and `Hour`.
+pub enum Time {}
+
+macro_rules! units {
+    ($($(#[$doc:meta])* $name:ident: $dimension:ident, $symbol:expr, $unscaled:expr, $scale:expr;)*) => {
+        $(
+            $(#[$doc])*
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+            pub enum $name {}
+
+            impl Unit for $name {
+                type Dimension = $dimension;
+                const SYMBOL: &'static str = $symbol;
+                fn factor() -> Decimal {
+                    Decimal::new($unscaled, $scale)
+                }
+            }
+        )*
+    };
+}
+
+units! {
+    /// The base unit of mass.
+    Kilogram: Mass, "kg", 1, 0;
+    Gram: Mass, "g", 1, 3;
+    /// The international avoirdupois pound, exactly 0.45359237 kg.
+    Pound: Mass, "lb", 45359237, 8;
+    /// The avoirdupois ounce, 1/16 lb.
+    Ounce: Mass, "oz", 28349523125, 12;
+    /// The base unit of volume.
+    Liter: Volume, "L", 1, 0;
+    Milliliter: Volume, "mL", 1, 3;
+    /// The US liquid gallon, exactly 3.785411784 L.
+    Gallon: Volume, "gal", 3785411784, 9;
+    /// The base unit of time.
+    Second: Time, "s", 1, 0;
+    Minute: Time, "min", 60, 0;
+    Hour: Time, "h", 3600, 0;
+}
+
+/// A `Decimal` amount of the unit `U`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash)]
+pub struct Quantity<U> {
+    value: Decimal,
+    unit: PhantomData<U>,
+}
+
+impl<U: Unit> Quantity<U> {
+    pub fn new(value: Decimal) -> Quantity<U> {
+        Quantity { value, unit: PhantomData }
+    }
+
+    /// The amount, without its unit.
+    pub fn value(&self) -> Decimal {
+        self.value
+    }
+
+    /// The same amount in unit `V`, exactly. Fails with `Inexact` if it has no finite
+    /// decimal expansion (as a kilogram in pounds doesn't), or `Overflow` if it doesn't
+    /// fit in a `Decimal`.
+    ///
+    /// The result has the scale multiplying and dividing by the factors gives it, plus
+    /// any places the division needs, e.g. 1.5 lb is 0.680388555 kg, with the 8 places
+    /// of the pound's factor added to the 1 of 1.5. It's never negative unless the
+    /// original scale was, so 2.5 kg is 2500 g rather than 2.5E+3 g.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{ArithmeticError, Decimal};
+    /// # use decimal::quantity::{Gram, Kilogram, Ounce, Pound, Quantity};
+    /// let weight = Quantity::<Pound>::new(Decimal::new(15, 1));
+    /// assert_eq!(Ok(Decimal::new(680388555, 9)), weight.convert::<Kilogram>().map(|q| q.value()));
+    /// assert_eq!(Ok(Decimal::new(24, 0)), weight.convert::<Ounce>().map(|q| q.value()));
+    /// assert_eq!(Err(ArithmeticError::Inexact),
+    ///            Quantity::<Gram>::new(Decimal::new(1, 0)).convert::<Pound>());
+    /// ```
+    pub fn convert<V: Unit<Dimension = U::Dimension>>(&self)
+                                                     -> Result<Quantity<V>, ArithmeticError> {
+        let (n, n_scale) = self.base_value();
+        let to = V::factor();
+        // The quotient ends after as many more places as the greater count of 2s or 5s
+        // in the denominator, once reduced, provided those are its only prime factors.
+        let mut rest = to.unscaled as i128 / gcd(n, to.unscaled as i128);
+        let mut places = 0i64;
+        for &prime in &[2, 5] {
+            let mut count = 0;
+            while rest % prime == 0 {
+                rest /= prime;
+                count += 1;
+            }
+            places = cmp::max(places, count);
+        }
+        if rest != 1 {
+            return Err(ArithmeticError::Inexact);
+        }
+        let scale = i32::try_from(n_scale - to.scale as i64 + places)
+            .map_err(|_| ArithmeticError::ScaleOverflow)?
+            .max(self.value.scale.min(0));
+        divide_to_scale(n, n_scale, to.unscaled as i128, to.scale as i64, scale, RoundingMode::Down)
+            .map(Quantity::new)
+    }
+
+    /// The same amount in unit `V`, rounded to `scale` according to `mode`. Fails with
+    /// `Overflow` if it doesn't fit in a `Decimal`.
+    pub fn convert_round<V: Unit<Dimension = U::Dimension>>(&self, scale: i32, mode: RoundingMode)
+                                                           -> Result<Quantity<V>, ArithmeticError> {
+        let (n, n_scale) = self.base_value();
+        let to = V::factor();
+        divide_to_scale(n, n_scale, to.unscaled as i128, to.scale as i64, scale, mode)
+            .map(Quantity::new)
+    }
+
+    /// The amount in the dimension's base unit, as an unscaled value and scale.
+    fn base_value(&self) -> (i128, i64) {
+        let from = U::factor();
+        (self.value.unscaled as i128 * from.unscaled as i128,
+         self.value.scale as i64 + from.scale as i64)
+    }
+}
+
+/// Writes the value and the unit's symbol, e.g. `2.5 kg`.
+impl<U: Unit> fmt::Display for Quantity<U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.value, U::SYMBOL)
+    }
+}
+
+/// Parses a decimal followed by the unit's symbol, with or without space between, e.g.
+/// `2.5 kg` or `2.5kg`. A different unit, even one ending in this one's symbol such as
+/// `mL` for liters, is reported as a missing one.
+///
+/// # Examples
+/// ```
+/// # use decimal::Decimal;
+/// # use decimal::quantity::{Liter, ParseQuantityError, Quantity};
+/// assert_eq!(Ok(Quantity::<Liter>::new(Decimal::new(75, 2))), "0.75 L".parse());
+/// assert_eq!(Err(ParseQuantityError::MissingUnit("L")), "2 gal".parse::<Quantity<Liter>>());
+/// ```
+impl<U: Unit> str::FromStr for Quantity<U> {
+    type Err = ParseQuantityError;
+
+    fn from_str(s: &str) -> Result<Quantity<U>, ParseQuantityError> {
+        let (number, unit) = match s.find(char::is_whitespace) {
+            Some(i) => (&s[..i], s[i..].trim_start()),
+            None => s.split_at(number_len(s)),
+        };
+        // Report a bad number over a wrong unit, so errors point at the bad character.
+        let value = number.parse()?;
+        if unit != U::SYMBOL {
+            return Err(ParseQuantityError::MissingUnit(U::SYMBOL));
+        }
+        Ok(Quantity::new(value))
+    }
+}
+
+/// The length of the longest prefix of `s` that could be part of a decimal, where an
+/// exponent marker only counts if a digit or sign follows it.
+fn number_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    bytes.iter().enumerate().position(|(i, &b)| {
+        let sign_or_digit = |c: u8| c.is_ascii_digit() || c == b'+' || c == b'-';
+        let exponent = (b == b'e' || b == b'E')
+            && bytes.get(i + 1).is_some_and(|&c| sign_or_digit(c));
+        !(sign_or_digit(b) || b == b'.' || exponent)
+    }).unwrap_or(bytes.len())
+}
+
+/// The error returned when parsing a `Quantity` fails.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseQuantityError {
+    /// The number isn't a valid decimal.
+    InvalidNumber(ParseDecimalError),
+    /// A valid decimal without the symbol of the unit being parsed, which is given.
+    MissingUnit(&'static str),
+}
+
+impl fmt::Display for ParseQuantityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseQuantityError::InvalidNumber(ref e) => e.fmt(f),
+            ParseQuantityError::MissingUnit(symbol) =>
+                write!(f, "decimal must be followed by '{}'", symbol),
+        }
+    }
+}
+
+impl error::Error for ParseQuantityError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            ParseQuantityError::InvalidNumber(ref e) => Some(e),
+            ParseQuantityError::MissingUnit(_) => None,
+        }
+    }
+}
+
+impl From<ParseDecimalError> for ParseQuantityError {
+    fn from(e: ParseDecimalError) -> ParseQuantityError {
+        ParseQuantityError::InvalidNumber(e)
+    }
+}
+
+impl<U: Unit> ops::Add for Quantity<U> {
+    type Output = Quantity<U>;
+    fn add(self, other: Quantity<U>) -> Quantity<U> {
+        Quantity::new(self.value + other.value)
+    }
+}
+
+impl<U: Unit> ops::Sub for Quantity<U> {
+    type Output = Quantity<U>;
+    fn sub(self, other: Quantity<U>) -> Quantity<U> {
+        Quantity::new(self.value - other.value)
+    }
+}
+
+/// Scales a quantity, e.g. the weight of 3 items.
+impl<U: Unit> ops::Mul<Decimal> for Quantity<U> {
+    type Output = Quantity<U>;
+    fn mul(self, factor: Decimal) -> Quantity<U> {
+        Quantity::new(self.value * factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gallon, Gram, Hour, Kilogram, Liter, Milliliter, Minute, Ounce, ParseQuantityError,
+                Pound, Quantity, Second};
+    use {ArithmeticError, Decimal, DecimalErrorKind};
+
+    fn q<U: super::Unit>(value: &str) -> Quantity<U> {
+        Quantity::new(value.parse().unwrap())
+    }
+
+    #[test]
+    fn converts_exactly() {
+        assert_eq!(Ok(q::<Gram>("2500")), q::<Kilogram>("2.5").convert());
+        assert_eq!(Ok(q::<Kilogram>("2.500")), q::<Gram>("2500").convert());
+        assert_eq!(Ok(q::<Kilogram>("0.45359237")), q::<Pound>("1").convert());
+        assert_eq!(Ok(q::<Pound>("0.5000")), q::<Ounce>("8").convert());
+        assert_eq!(Ok(q::<Liter>("37.854117840")), q::<Gallon>("10").convert());
+        assert_eq!(Ok(q::<Milliliter>("3785.411784")), q::<Gallon>("1").convert());
+        assert_eq!(Ok(q::<Hour>("0.25")), q::<Minute>("15").convert());
+        assert_eq!(Ok(q::<Minute>("0.0125")), q::<Second>("0.75").convert());
+        assert_eq!(Ok(q::<Second>("9000.0")), q::<Hour>("2.5").convert());
+        assert_eq!(Ok(q::<Pound>("0")), q::<Kilogram>("0").convert());
+        assert_eq!(Ok(q::<Pound>("-2")), q::<Kilogram>("-0.90718474").convert());
+    }
+
+    #[test]
+    fn conversions_that_would_not_terminate_are_inexact() {
+        assert_eq!(Err(ArithmeticError::Inexact), q::<Kilogram>("1").convert::<Pound>());
+        assert_eq!(Err(ArithmeticError::Inexact), q::<Liter>("1").convert::<Gallon>());
+        assert_eq!(Err(ArithmeticError::Inexact), q::<Minute>("1").convert::<Hour>());
+        assert_eq!(Err(ArithmeticError::Overflow),
+                   q::<Kilogram>("9223372036854775807").convert::<Gram>());
+        assert_eq!(Err(ArithmeticError::ScaleOverflow),
+                   Quantity::<Gram>::new(Decimal::new(1, i32::MAX)).convert::<Kilogram>());
+    }
+
+    #[test]
+    fn converts_with_rounding() {
+        use RoundingMode::*;
+        assert_eq!(Ok(q::<Pound>("2.20")), q::<Kilogram>("1").convert_round(2, Down));
+        assert_eq!(Ok(q::<Pound>("2.21")), q::<Kilogram>("1").convert_round(2, Up));
+        assert_eq!(Ok(q::<Gallon>("0.264")), q::<Liter>("1").convert_round(3, HalfEven));
+        assert_eq!(Ok(q::<Hour>("0.017")), q::<Minute>("1").convert_round(3, HalfUp));
+        assert_eq!(Ok(q::<Kilogram>("0.45")), q::<Pound>("1").convert_round(2, HalfUp));
+        assert_eq!(Err(ArithmeticError::Overflow),
+                   q::<Kilogram>("9223372036854775807").convert_round::<Gram>(0, Down));
+    }
+
+    #[test]
+    fn adds_subtracts_and_scales_in_one_unit() {
+        assert_eq!(q::<Kilogram>("3.75"), q::<Kilogram>("2.5") + q::<Kilogram>("1.25"));
+        assert_eq!(q::<Kilogram>("-0.5"), q::<Kilogram>("1") - q::<Kilogram>("1.5"));
+        assert_eq!(q::<Liter>("2.25"), q::<Liter>("0.75") * Decimal::new(3, 0));
+        assert!(q::<Second>("59.9") < q::<Second>("60"));
+    }
+
+    #[test]
+    fn displays_and_parses_with_symbol() {
+        assert_eq!("2.5 kg", q::<Kilogram>("2.5").to_string());
+        assert_eq!(Ok(q::<Kilogram>("2.5")), "2.5 kg".parse());
+        assert_eq!(Ok(q::<Gallon>("1.0")), "1.0gal".parse());
+        assert_eq!(Ok(q::<Minute>("-3")), "-3  min".parse());
+        assert_eq!(Ok(q::<Milliliter>("250")), "250 mL".parse());
+        let missing = Err(ParseQuantityError::MissingUnit("kg"));
+        assert_eq!(missing, "2.5".parse::<Quantity<Kilogram>>());
+        assert_eq!(missing, "2.5 lb".parse::<Quantity<Kilogram>>());
+        assert_eq!(missing, "2.5 kgs".parse::<Quantity<Kilogram>>());
+        assert_eq!(missing, "2.5lb".parse::<Quantity<Kilogram>>());
+        let kind = |s: &str| match s.parse::<Quantity<Kilogram>>().unwrap_err() {
+            ParseQuantityError::InvalidNumber(e) => e.kind().clone(),
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(DecimalErrorKind::InvalidChar { character: 'x', byte_index: 3, char_index: 3 },
+                   kind("2.5x kg"));
+        assert_eq!(DecimalErrorKind::InvalidChar { character: '.', byte_index: 3, char_index: 3 },
+                   kind("1.2.3kg"));
+        assert_eq!(DecimalErrorKind::Empty, kind(" kg"));
+        assert_eq!(DecimalErrorKind::NoDigits, kind("- kg"));
+    }
+
+    #[test]
+    fn symbols_match_whole_unit_tokens() {
+        assert_eq!(Err(ParseQuantityError::MissingUnit("L")), "250 mL".parse::<Quantity<Liter>>());
+        assert_eq!(Err(ParseQuantityError::MissingUnit("L")), "250mL".parse::<Quantity<Liter>>());
+        assert_eq!(Err(ParseQuantityError::MissingUnit("g")), "2 kg".parse::<Quantity<Gram>>());
+        assert_eq!(Err(ParseQuantityError::MissingUnit("g")), "2kg".parse::<Quantity<Gram>>());
+        assert_eq!(Ok(q::<Gram>("1.5E+3")), "1.5e3g".parse());
+        assert_eq!(Ok(q::<Gram>("2E-3")), "2E-3 g".parse());
+        assert_eq!(Ok(q::<Liter>("2")), "2L".parse());
+    }
+}
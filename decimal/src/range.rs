@@ -0,0 +1,179 @@
+//! Iterating over evenly spaced `Decimal`s, such as price ladders and tick grids.
+//!
+//! Each value is the previous one plus the step, computed exactly, so there's no float
+//! drift: stepping from `0.00` to `5.00` by `0.05` lands on `5.00` itself. The start,
+//! end and step are first aligned to the widest of their scales, so every value the
+//! iterator yields has that scale.
+//!
+//! # Examples
+//! ```
+//! # use decimal::Decimal;
+//! let ticks: Vec<Decimal> = Decimal::range(Decimal::new(0, 0), Decimal::new(5, 0),
+//!                                          Decimal::new(5, 2)).collect();
+//! assert_eq!(100, ticks.len());
+//! assert_eq!(Decimal::new(495, 2), ticks[99]);
+//!
+//! let countdown: Vec<String> = Decimal::range_inclusive(
+//!     Decimal::new(1, 0), Decimal::new(0, 0), Decimal::new(-25, 2))
+//!     .map(|d| d.to_string()).collect();
+//! assert_eq!(vec!["1.00", "0.75", "0.50", "0.25", "0.00"], countdown);
+//! ```
+
+use std::iter::FusedIterator;
+
+use Decimal;
+
+/// An iterator over `Decimal`s from a start toward an end by a fixed step. Created by
+/// `Decimal::range` and `Decimal::range_inclusive`.
+#[derive(Debug, Clone)]
+pub struct DecimalRange {
+    next: Option<Decimal>,
+    end: Decimal,
+    step: Decimal,
+    inclusive: bool,
+}
+
+impl DecimalRange {
+    fn new(start: Decimal, end: Decimal, step: Decimal, inclusive: bool) -> DecimalRange {
+        assert!(step.unscaled != 0, "decimal range step must not be zero");
+        let scale = start.scale.max(end.scale).max(step.scale);
+        DecimalRange {
+            next: Some(start.adjust_scale(scale)),
+            end: end.adjust_scale(scale),
+            step: step.adjust_scale(scale),
+            inclusive,
+        }
+    }
+
+    fn in_range(&self, value: Decimal) -> bool {
+        match (self.step.unscaled > 0, self.inclusive) {
+            (true, true) => value <= self.end,
+            (true, false) => value < self.end,
+            (false, true) => value >= self.end,
+            (false, false) => value > self.end,
+        }
+    }
+}
+
+impl Iterator for DecimalRange {
+    type Item = Decimal;
+
+    fn next(&mut self) -> Option<Decimal> {
+        let current = self.next.take().filter(|&value| self.in_range(value))?;
+        if current != self.end {
+            self.next = current.try_add(self.step).ok();
+        }
+        Some(current)
+    }
+}
+
+impl FusedIterator for DecimalRange {}
+
+impl Decimal {
+    /// Iterates from `start` toward `end`, excluding `end`, adding `step` each time.
+    /// A negative `step` counts down. All values have the widest scale of the three
+    /// arguments.
+    ///
+    /// Panics if `step` is zero. The iteration ends early if the next step would overflow.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::Decimal;
+    /// let values: Vec<Decimal> = Decimal::range(Decimal::new(1, 0), Decimal::new(2, 0),
+    ///                                           Decimal::new(25, 2)).collect();
+    /// assert_eq!(vec![Decimal::new(100, 2), Decimal::new(125, 2), Decimal::new(150, 2),
+    ///                 Decimal::new(175, 2)],
+    ///            values);
+    /// ```
+    pub fn range(start: Decimal, end: Decimal, step: Decimal) -> DecimalRange {
+        DecimalRange::new(start, end, step, false)
+    }
+
+    /// Like `range`, but includes `end` if a step lands on it exactly.
+    pub fn range_inclusive(start: Decimal, end: Decimal, step: Decimal) -> DecimalRange {
+        DecimalRange::new(start, end, step, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use Decimal;
+
+    fn collect(range: super::DecimalRange) -> Vec<String> {
+        range.map(|d| d.to_string()).collect()
+    }
+
+    #[test]
+    fn steps_exactly_to_the_end() {
+        let grid: Vec<Decimal> = Decimal::range_inclusive(
+            Decimal::new(0, 2), Decimal::new(500, 2), Decimal::new(5, 2)).collect();
+        assert_eq!(101, grid.len());
+        assert_eq!(Decimal::new(500, 2), grid[100]);
+        assert!(grid.iter().enumerate().all(|(i, d)| *d == Decimal::new(i as i64 * 5, 2)));
+    }
+
+    #[test]
+    fn aligns_to_the_widest_scale() {
+        assert_eq!(vec!["1.000", "1.125", "1.250"],
+                   collect(Decimal::range_inclusive(Decimal::new(1, 0), Decimal::new(125, 2),
+                                                    Decimal::new(125, 3))));
+        assert_eq!(vec!["0.0", "0.5", "1.0"],
+                   collect(Decimal::range(Decimal::new(0, 0), Decimal::new(15, 1),
+                                          Decimal::new(5, 1))));
+    }
+
+    #[test]
+    fn counts_down_with_a_negative_step() {
+        assert_eq!(vec!["3", "1", "-1"],
+                   collect(Decimal::range(Decimal::new(3, 0), Decimal::new(-3, 0),
+                                          Decimal::new(-2, 0))));
+        assert_eq!(vec!["3", "1", "-1", "-3"],
+                   collect(Decimal::range_inclusive(Decimal::new(3, 0), Decimal::new(-3, 0),
+                                                    Decimal::new(-2, 0))));
+    }
+
+    #[test]
+    fn stops_short_of_an_end_it_would_step_over() {
+        assert_eq!(vec!["0.0", "0.4", "0.8"],
+                   collect(Decimal::range_inclusive(Decimal::new(0, 0), Decimal::new(1, 0),
+                                                    Decimal::new(4, 1))));
+    }
+
+    #[test]
+    fn empty_when_the_step_points_away_from_the_end() {
+        assert!(collect(Decimal::range(Decimal::new(1, 0), Decimal::new(1, 0),
+                                       Decimal::new(1, 0))).is_empty());
+        assert!(collect(Decimal::range(Decimal::new(1, 0), Decimal::new(2, 0),
+                                       Decimal::new(-1, 0))).is_empty());
+        assert_eq!(vec!["1"],
+                   collect(Decimal::range_inclusive(Decimal::new(1, 0), Decimal::new(1, 0),
+                                                    Decimal::new(-1, 0))));
+    }
+
+    #[test]
+    fn reaching_an_end_at_the_limit_does_not_overflow() {
+        let max = Decimal::new(i64::MAX, 0);
+        let values: Vec<Decimal> = Decimal::range_inclusive(
+            Decimal::new(i64::MAX - 2, 0), max, Decimal::new(1, 0)).collect();
+        assert_eq!(Some(&max), values.last());
+        assert_eq!(3, values.len());
+    }
+
+    #[test]
+    fn a_step_past_the_limit_ends_the_iteration() {
+        let mut range = Decimal::range(Decimal::new(i64::MAX - 1, 0), Decimal::new(i64::MAX, 0),
+                                       Decimal::new(2, 0));
+        assert_eq!(Some(Decimal::new(i64::MAX - 1, 0)), range.next());
+        assert_eq!(None, range.next());
+
+        let last = Decimal::range("0".parse().unwrap(), "9.2E+18".parse().unwrap(),
+                                  "1E+18".parse().unwrap()).last();
+        assert_eq!(Some(Decimal::new(9_000_000_000_000_000_000, 0)), last);
+    }
+
+    #[test]
+    #[should_panic(expected = "step must not be zero")]
+    fn zero_step_panics() {
+        Decimal::range(Decimal::new(0, 0), Decimal::new(1, 0), Decimal::new(0, 3));
+    }
+}
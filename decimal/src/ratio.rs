@@ -0,0 +1,281 @@
+//! Exact rational numbers, for keeping intermediate results (such as pro-rata shares)
+//! exact until a final rounding back to `Decimal`.
+//!
+//! # Examples
+//! ```
+//! # use std::convert::TryFrom;
+//! # use decimal::{Decimal, RoundingMode};
+//! # use decimal::ratio::Ratio;
+//! let total = Ratio::try_from(Decimal::new(10000, 2)).unwrap();
+//! let share = total * Ratio::new(1, 3);
+//! assert_eq!("100/3", share.to_string());
+//! assert_eq!(Ok(Decimal::new(3333, 2)), share.to_decimal(2, RoundingMode::HalfEven));
+//! assert_eq!(total, share + share + share);
+//! ```
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops;
+
+use {divide_rounded, gcd, ArithmeticError, Decimal, RoundingMode};
+
+/// A fraction of `i128`s, always kept in lowest terms with a positive denominator.
+///
+/// Arithmetic is exact and, like `Decimal`'s, panics on overflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ratio {
+    numer: i128,
+    denom: i128,
+}
+
+impl Ratio {
+    /// `numer / denom` in lowest terms.
+    ///
+    /// Panics if `denom` is zero.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::ratio::Ratio;
+    /// let r = Ratio::new(6, -4);
+    /// assert_eq!((-3, 2), (r.numer(), r.denom()));
+    /// ```
+    pub fn new(numer: i128, denom: i128) -> Ratio {
+        assert!(denom != 0, "ratio denominator must not be zero");
+        if numer == 0 {
+            // gcd(0, denom) is |denom|, which doesn't fit in an i128 for i128::MIN.
+            return Ratio::from_integer(0);
+        }
+        let divisor = gcd(numer, denom);
+        let (numer, denom) = (numer / divisor, denom / divisor);
+        if denom < 0 {
+            Ratio { numer: neg(numer), denom: neg(denom) }
+        } else {
+            Ratio { numer, denom }
+        }
+    }
+
+    fn from_integer(n: i128) -> Ratio {
+        Ratio { numer: n, denom: 1 }
+    }
+
+    pub fn numer(&self) -> i128 {
+        self.numer
+    }
+
+    pub fn denom(&self) -> i128 {
+        self.denom
+    }
+
+    /// The value at `scale`, rounded according to `mode`. Fails with
+    /// `ArithmeticError::Overflow` if the result doesn't fit in a `Decimal`, or if the
+    /// numerator times 10^`scale` doesn't fit in an `i128`.
+    pub fn to_decimal(&self, scale: i32, mode: RoundingMode) -> Result<Decimal, ArithmeticError> {
+        let power = 10i128.checked_pow(scale.unsigned_abs());
+        let (numer, denom) = if scale >= 0 {
+            (power.and_then(|p| self.numer.checked_mul(p)), Some(self.denom))
+        } else {
+            (Some(self.numer), power.and_then(|p| self.denom.checked_mul(p)))
+        };
+        let unscaled = match (numer, denom) {
+            (Some(numer), Some(denom)) => divide_rounded(numer, denom, mode),
+            // Past i128, the denominator dwarfs any numerator, as in downscale_rounded.
+            (Some(numer), None) => divide_rounded(numer, i128::MAX, mode),
+            (None, _) if self.numer == 0 => 0,
+            (None, _) => return Err(ArithmeticError::Overflow),
+        };
+        i64::try_from(unscaled).map(|unscaled| Decimal::new(unscaled, scale))
+            .map_err(|_| ArithmeticError::Overflow)
+    }
+}
+
+fn neg(n: i128) -> i128 {
+    n.checked_neg().expect("attempt to negate with overflow")
+}
+
+fn checked(n: Option<i128>, op: &str) -> i128 {
+    n.unwrap_or_else(|| panic!("attempt to {} with overflow", op))
+}
+
+impl From<i64> for Ratio {
+    fn from(n: i64) -> Ratio {
+        Ratio::from_integer(n as i128)
+    }
+}
+
+/// Exact for scales from -19 to 38, which covers any decimal with a reasonable scale.
+/// Outside that, a decimal whose reduced numerator or denominator doesn't fit in an
+/// `i128` is reported as `ArithmeticError::Overflow`.
+impl TryFrom<Decimal> for Ratio {
+    type Error = ArithmeticError;
+    fn try_from(d: Decimal) -> Result<Ratio, ArithmeticError> {
+        if d.unscaled == 0 {
+            return Ok(Ratio::from(0));
+        }
+        if d.scale < 0 {
+            return 10i128.checked_pow(d.scale.unsigned_abs())
+                .and_then(|power| (d.unscaled as i128).checked_mul(power))
+                .map(Ratio::from_integer)
+                .ok_or(ArithmeticError::Overflow);
+        }
+        // Cancel the 2s and 5s the unscaled value shares with 10^scale before raising
+        // anything to a power, so only a reduced denominator too large to hold fails.
+        let scale = d.scale as u32;
+        let twos = d.unscaled.trailing_zeros().min(scale);
+        let mut numer = d.unscaled as i128 >> twos;
+        let mut fives = 0;
+        while fives < scale && numer % 5 == 0 {
+            numer /= 5;
+            fives += 1;
+        }
+        2i128.checked_pow(scale - twos)
+            .and_then(|p| 5i128.checked_pow(scale - fives).and_then(|q| p.checked_mul(q)))
+            .map(|denom| Ratio::new(numer, denom))
+            .ok_or(ArithmeticError::Overflow)
+    }
+}
+
+impl fmt::Display for Ratio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.denom == 1 {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+impl ops::Neg for Ratio {
+    type Output = Ratio;
+    fn neg(self) -> Ratio {
+        Ratio { numer: neg(self.numer), denom: self.denom }
+    }
+}
+
+impl ops::Add for Ratio {
+    type Output = Ratio;
+    fn add(self, other: Ratio) -> Ratio {
+        // Over the least common denominator, to put off overflow as long as possible.
+        let divisor = gcd(self.denom, other.denom);
+        let (self_factor, other_factor) = (other.denom / divisor, self.denom / divisor);
+        let numer = self.numer.checked_mul(self_factor)
+            .and_then(|a| other.numer.checked_mul(other_factor).and_then(|b| a.checked_add(b)));
+        let denom = self.denom.checked_mul(self_factor);
+        Ratio::new(checked(numer, "add"), checked(denom, "add"))
+    }
+}
+
+impl ops::Sub for Ratio {
+    type Output = Ratio;
+    fn sub(self, other: Ratio) -> Ratio {
+        self + -other
+    }
+}
+
+impl ops::Mul for Ratio {
+    type Output = Ratio;
+    fn mul(self, other: Ratio) -> Ratio {
+        // Cancelling across first keeps the products as small as they can be.
+        let a = gcd(self.numer, other.denom);
+        let b = gcd(other.numer, self.denom);
+        let numer = (self.numer / a).checked_mul(other.numer / b);
+        let denom = (self.denom / b).checked_mul(other.denom / a);
+        Ratio::new(checked(numer, "multiply"), checked(denom, "multiply"))
+    }
+}
+
+impl ops::Div for Ratio {
+    type Output = Ratio;
+    fn div(self, other: Ratio) -> Ratio {
+        assert!(other.numer != 0, "attempt to divide by zero");
+        self * Ratio::new(other.denom, other.numer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::Ratio;
+    use {ArithmeticError, Decimal};
+    use RoundingMode::*;
+
+    #[test]
+    fn keeps_lowest_terms() {
+        assert_eq!(Ratio::new(1, 2), Ratio::new(-3, -6));
+        assert_eq!("-1/2", Ratio::new(3, -6).to_string());
+        assert_eq!("0", Ratio::new(0, -5).to_string());
+        assert_eq!((0, 1), (Ratio::new(0, i128::MIN).numer(), Ratio::new(0, i128::MIN).denom()));
+        assert_eq!("7", Ratio::new(14, 2).to_string());
+    }
+
+    #[test]
+    fn arithmetic_is_exact() {
+        let third = Ratio::new(1, 3);
+        let sixth = Ratio::new(1, 6);
+        assert_eq!(Ratio::new(1, 2), third + sixth);
+        assert_eq!(Ratio::new(1, 6), third - sixth);
+        assert_eq!(Ratio::new(1, 18), third * sixth);
+        assert_eq!(Ratio::from(2), third / sixth);
+        assert_eq!(Ratio::from(1), third + third + third);
+        assert_eq!(Ratio::new(-1, 3), -third);
+    }
+
+    #[test]
+    fn cancels_before_overflowing() {
+        let big = Ratio::new(i128::MAX, 3);
+        assert_eq!(Ratio::from_integer(i128::MAX), big * Ratio::from(3));
+        assert_eq!(Ratio::from(1), big / big);
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to multiply with overflow")]
+    fn overflow_panics() {
+        let _ = Ratio::from_integer(i128::MAX) * Ratio::from(2);
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to divide by zero")]
+    fn dividing_by_zero_panics() {
+        let _ = Ratio::from(1) / Ratio::from(0);
+    }
+
+    #[test]
+    fn converts_decimals_exactly() {
+        assert_eq!(Ok(Ratio::new(5, 4)), Ratio::try_from(Decimal::new(125, 2)));
+        assert_eq!(Ok(Ratio::from(-1200)), Ratio::try_from(Decimal::new(-12, -2)));
+        assert_eq!(Ok(Ratio::new(1, 100_000_000_000_000_000_000_000_000_000_000_000_000)),
+                   Ratio::try_from(Decimal::new(1, 38)));
+        assert_eq!(Ok(Ratio::from(0)), Ratio::try_from(Decimal::new(0, 100)));
+        assert_eq!(Err(ArithmeticError::Overflow), Ratio::try_from(Decimal::new(1, 39)));
+        assert_eq!(Ok(Ratio::new(1, 100_000_000_000_000_000_000_000_000_000_000_000_000)),
+                   Ratio::try_from(Decimal::new(10, 39)));
+        assert_eq!(Ok(Ratio::new(-1, 5i128.pow(50))),
+                   Ratio::try_from(Decimal::new(-(1 << 50), 50)));
+        assert_eq!(Ok(Ratio::new(1, 2)),
+                   Ratio::try_from(Decimal::new(5_000_000_000_000_000_000, 19)));
+        assert_eq!(Ok(Ratio::new(-(1 << 62), 5)), Ratio::try_from(Decimal::new(i64::MIN, 1)));
+        assert!(Ratio::try_from(Decimal::new(i64::MIN, -19)).is_ok());
+        assert_eq!(Err(ArithmeticError::Overflow), Ratio::try_from(Decimal::new(1, -39)));
+    }
+
+    #[test]
+    fn converts_back_with_rounding() {
+        let two_thirds = Ratio::new(2, 3);
+        assert_eq!(Ok(Decimal::new(67, 2)), two_thirds.to_decimal(2, HalfEven));
+        assert_eq!(Ok(Decimal::new(66, 2)), two_thirds.to_decimal(2, Down));
+        assert_eq!(Ok(Decimal::new(-67, 2)), (-two_thirds).to_decimal(2, Floor));
+        assert_eq!(Ok(Decimal::new(2, -2)), Ratio::from(250).to_decimal(-2, HalfEven));
+        assert_eq!(Ok(Decimal::new(1, -40)), Ratio::from(1).to_decimal(-40, Up));
+        assert_eq!(Ok(Decimal::new(0, 40)), Ratio::from(0).to_decimal(40, Up));
+        assert_eq!(Err(ArithmeticError::Overflow), Ratio::from(1).to_decimal(40, Up));
+        assert_eq!(Err(ArithmeticError::Overflow), Ratio::from(i64::MAX).to_decimal(1, Up));
+    }
+
+    #[test]
+    fn round_trips_decimals() {
+        for &d in &[Decimal::new(125, 2), Decimal::new(-9, 7), Decimal::new(i64::MIN, 0)] {
+            let ratio = Ratio::try_from(d).unwrap();
+            assert_eq!(Ok(d), ratio.to_decimal(d.scale, Down));
+        }
+    }
+}
@@ -0,0 +1,410 @@
+//! Exact descriptive statistics over collections of `Decimal`s.
+//!
+//! Everything is computed exactly from the inputs (aligned to the widest scale among
+//! them), so the only rounding is a single final step to the scale and `RoundingMode`
+//! the caller asks for. Functions that need at least one value return
+//! `StatsError::Empty` rather than panicking, and intermediate results that don't fit
+//! in an `i128` are reported as `StatsError::Overflow`.
+//!
+//! # Examples
+//! ```
+//! # use decimal::{Decimal, RoundingMode};
+//! # use decimal::stats;
+//! let prices = vec![Decimal::new(1000, 2), Decimal::new(1250, 2), Decimal::new(2, 0)];
+//! assert_eq!(Ok(Decimal::new(2450, 2)), stats::sum(prices.clone()));
+//! assert_eq!(Ok(Decimal::new(817, 2)), stats::mean(prices.clone(), 2, RoundingMode::HalfEven));
+//! assert_eq!(Ok(Decimal::new(1000, 2)), stats::median(prices));
+//! ```
+
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+
+use percent::Percent;
+use ratio::Ratio;
+use {ArithmeticError, Decimal, RoundingMode};
+
+/// The ways a statistic can fail to be computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatsError {
+    /// There were no values.
+    Empty,
+    /// There were too few values, e.g. one value for a sample variance.
+    NotEnoughValues,
+    /// The weights of a weighted average summed to zero.
+    ZeroWeight,
+    /// A percentile outside 0% to 100%.
+    InvalidPercentile,
+    /// A result or intermediate value was too large.
+    Overflow,
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            StatsError::Empty => "no values to compute statistic from",
+            StatsError::NotEnoughValues => "too few values to compute statistic from",
+            StatsError::ZeroWeight => "weights sum to zero",
+            StatsError::InvalidPercentile => "percentile must be between 0% and 100%",
+            StatsError::Overflow => "statistic overflowed",
+        })
+    }
+}
+
+impl error::Error for StatsError {}
+
+impl From<ArithmeticError> for StatsError {
+    fn from(_: ArithmeticError) -> StatsError {
+        StatsError::Overflow
+    }
+}
+
+/// Unscaled values brought to a common scale.
+struct Aligned {
+    values: Vec<i128>,
+    scale: i32,
+}
+
+impl Aligned {
+    fn new<I: IntoIterator<Item = Decimal>>(values: I) -> Result<Aligned, StatsError> {
+        let decimals: Vec<Decimal> = values.into_iter().collect();
+        let scale = decimals.iter().map(|d| d.scale).max().ok_or(StatsError::Empty)?;
+        let values = decimals.iter()
+            .map(|d| upscale((d.scale as i64 - scale as i64).unsigned_abs(), d.unscaled as i128))
+            .collect::<Result<_, _>>()?;
+        Ok(Aligned { values, scale })
+    }
+
+    fn len(&self) -> i128 {
+        self.values.len() as i128
+    }
+
+    fn sum(&self) -> Result<i128, StatsError> {
+        self.values.iter().try_fold(0i128, |sum, &v| checked(sum.checked_add(v)))
+    }
+
+    fn sorted(mut self) -> Aligned {
+        self.values.sort_unstable();
+        self
+    }
+}
+
+/// `n` times 10^`places`.
+fn upscale(places: u64, n: i128) -> Result<i128, StatsError> {
+    if n == 0 {
+        return Ok(0);
+    }
+    let factor = u32::try_from(places).ok().and_then(|p| 10i128.checked_pow(p));
+    checked(factor.and_then(|f| n.checked_mul(f)))
+}
+
+fn checked(n: Option<i128>) -> Result<i128, StatsError> {
+    n.ok_or(StatsError::Overflow)
+}
+
+/// `numer / denom` in units of 10^-`from_scale`, rounded to `scale`.
+fn quotient(numer: i128, denom: i128, from_scale: i32, scale: i32, mode: RoundingMode)
+            -> Result<Decimal, StatsError> {
+    let places = scale.checked_sub(from_scale).ok_or(StatsError::Overflow)?;
+    let rounded = Ratio::new(numer, denom).to_decimal(places, mode)?;
+    Ok(Decimal::new(rounded.unscaled, scale))
+}
+
+fn to_decimal(unscaled: i128, scale: i32) -> Result<Decimal, StatsError> {
+    i64::try_from(unscaled).map(|u| Decimal::new(u, scale)).map_err(|_| StatsError::Overflow)
+}
+
+/// The exact sum, at the widest scale among `values`. The sum of no values is zero.
+pub fn sum<I: IntoIterator<Item = Decimal>>(values: I) -> Result<Decimal, StatsError> {
+    match Aligned::new(values) {
+        Ok(aligned) => to_decimal(aligned.sum()?, aligned.scale),
+        Err(StatsError::Empty) => Ok(Decimal::new(0, 0)),
+        Err(e) => Err(e),
+    }
+}
+
+/// The arithmetic mean, rounded to `scale` according to `mode`.
+pub fn mean<I: IntoIterator<Item = Decimal>>(values: I, scale: i32, mode: RoundingMode)
+                                             -> Result<Decimal, StatsError> {
+    let aligned = Aligned::new(values)?;
+    quotient(aligned.sum()?, aligned.len(), aligned.scale, scale, mode)
+}
+
+/// The middle value, or the exact mean of the two middle values if there are an even
+/// number. The result has the widest scale among `values`, or one more if the two
+/// middle values' mean needs a `.5`.
+///
+/// # Examples
+/// ```
+/// # use decimal::Decimal;
+/// # use decimal::stats;
+/// let values = vec![Decimal::new(4, 0), Decimal::new(1, 0), Decimal::new(3, 0), Decimal::new(8, 0)];
+/// assert_eq!(Ok(Decimal::new(35, 1)), stats::median(values));
+/// ```
+pub fn median<I: IntoIterator<Item = Decimal>>(values: I) -> Result<Decimal, StatsError> {
+    let aligned = Aligned::new(values)?.sorted();
+    let middle = aligned.values.len() / 2;
+    if aligned.values.len() % 2 == 1 {
+        return to_decimal(aligned.values[middle], aligned.scale);
+    }
+    let total = checked(aligned.values[middle - 1].checked_add(aligned.values[middle]))?;
+    if total % 2 == 0 {
+        to_decimal(total / 2, aligned.scale)
+    } else {
+        let scale = aligned.scale.checked_add(1).ok_or(StatsError::Overflow)?;
+        to_decimal(checked(total.checked_mul(5))?, scale)
+    }
+}
+
+/// The `p`th percentile, interpolating linearly between the closest ranks (as
+/// spreadsheets' `PERCENTILE.INC` does), rounded to `scale` according to `mode`.
+///
+/// # Examples
+/// ```
+/// # use decimal::{Decimal, RoundingMode};
+/// # use decimal::stats;
+/// let values: Vec<Decimal> = (1..=10).map(|n| Decimal::new(n, 0)).collect();
+/// let p90 = "90%".parse().unwrap();
+/// assert_eq!(Ok(Decimal::new(910, 2)), stats::percentile(values, p90, 2, RoundingMode::HalfEven));
+/// ```
+pub fn percentile<I: IntoIterator<Item = Decimal>>(values: I, p: Percent, scale: i32,
+                                                   mode: RoundingMode)
+                                                   -> Result<Decimal, StatsError> {
+    let p = Ratio::try_from(p.value())?;
+    if p.numer() < 0 || p.numer() > checked(p.denom().checked_mul(100))? {
+        return Err(StatsError::InvalidPercentile);
+    }
+    let aligned = Aligned::new(values)?.sorted();
+    // The rank (counting from zero) is (n - 1) * p / 100, split into a whole rank and
+    // a fraction `remainder / denom` of the way to the next one.
+    let rank_numer = checked(p.numer().checked_mul(aligned.len() - 1))?;
+    let denom = checked(p.denom().checked_mul(100))?;
+    let (rank, remainder) = ((rank_numer / denom) as usize, rank_numer % denom);
+    let low = aligned.values[rank];
+    if remainder == 0 {
+        return quotient(low, 1, aligned.scale, scale, mode);
+    }
+    let gap = checked(aligned.values[rank + 1].checked_sub(low))?;
+    let numer = checked(low.checked_mul(denom)
+        .and_then(|l| gap.checked_mul(remainder).and_then(|g| l.checked_add(g))))?;
+    quotient(numer, denom, aligned.scale, scale, mode)
+}
+
+/// The mean of the values weighted by the weights, `Σ(value × weight) / Σweight`,
+/// rounded to `scale` according to `mode`. Weights may be negative, but must not sum
+/// to zero.
+///
+/// # Examples
+/// ```
+/// # use decimal::{Decimal, RoundingMode};
+/// # use decimal::stats;
+/// let fills = vec![(Decimal::new(1000, 2), Decimal::new(300, 0)),
+///                  (Decimal::new(1010, 2), Decimal::new(100, 0))];
+/// assert_eq!(Ok(Decimal::new(10025, 3)),
+///            stats::weighted_average(fills, 3, RoundingMode::HalfEven));
+/// ```
+pub fn weighted_average<I>(pairs: I, scale: i32, mode: RoundingMode) -> Result<Decimal, StatsError>
+    where I: IntoIterator<Item = (Decimal, Decimal)> {
+    let (values, weights): (Vec<Decimal>, Vec<Decimal>) = pairs.into_iter().unzip();
+    let values = Aligned::new(values)?;
+    let weights = Aligned::new(weights)?;
+    let weighted_sum = values.values.iter().zip(weights.values.iter())
+        .try_fold(0i128, |sum, (&v, &w)| checked(v.checked_mul(w).and_then(|p| sum.checked_add(p))))?;
+    let total_weight = weights.sum()?;
+    if total_weight == 0 {
+        return Err(StatsError::ZeroWeight);
+    }
+    // The weights' scale cancels out of the quotient.
+    quotient(weighted_sum, total_weight, values.scale, scale, mode)
+}
+
+/// Whether a variance describes the values themselves or a sample from a larger
+/// population.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Variance {
+    /// Divides by `n`.
+    Population,
+    /// Divides by `n - 1` (Bessel's correction). Needs at least two values.
+    Sample,
+}
+
+/// The exact variance, as a fraction of unscaled values squared (at scale twice the
+/// values' scale).
+fn exact_variance<I>(values: I, kind: Variance) -> Result<(i128, i128, i32), StatsError>
+    where I: IntoIterator<Item = Decimal> {
+    let aligned = Aligned::new(values)?;
+    let n = aligned.len();
+    if kind == Variance::Sample && n < 2 {
+        return Err(StatsError::NotEnoughValues);
+    }
+    // n Σx² - (Σx)², over n² or n(n - 1).
+    let sum = aligned.sum()?;
+    let sum_of_squares = aligned.values.iter()
+        .try_fold(0i128, |total, &v| checked(v.checked_mul(v).and_then(|sq| total.checked_add(sq))))?;
+    let numer = checked(n.checked_mul(sum_of_squares)
+        .and_then(|a| sum.checked_mul(sum).and_then(|b| a.checked_sub(b))))?;
+    let denom = checked(n.checked_mul(if kind == Variance::Sample { n - 1 } else { n }))?;
+    let scale = aligned.scale.checked_mul(2).ok_or(StatsError::Overflow)?;
+    Ok((numer, denom, scale))
+}
+
+/// The variance, rounded to `scale` according to `mode`.
+pub fn variance<I>(values: I, kind: Variance, scale: i32, mode: RoundingMode)
+                   -> Result<Decimal, StatsError>
+    where I: IntoIterator<Item = Decimal> {
+    let (numer, denom, from_scale) = exact_variance(values, kind)?;
+    quotient(numer, denom, from_scale, scale, mode)
+}
+
+/// The standard deviation (the square root of the exact variance), rounded to `scale`
+/// according to `mode`.
+///
+/// # Examples
+/// ```
+/// # use decimal::{Decimal, RoundingMode};
+/// # use decimal::stats::{self, Variance};
+/// let values: Vec<Decimal> = [2, 4, 4, 4, 5, 5, 7, 9].iter().map(|&n| Decimal::new(n, 0)).collect();
+/// assert_eq!(Ok(Decimal::new(200, 2)),
+///            stats::std_dev(values.clone(), Variance::Population, 2, RoundingMode::HalfEven));
+/// assert_eq!(Ok(Decimal::new(214, 2)),
+///            stats::std_dev(values, Variance::Sample, 2, RoundingMode::HalfEven));
+/// ```
+pub fn std_dev<I>(values: I, kind: Variance, scale: i32, mode: RoundingMode)
+                  -> Result<Decimal, StatsError>
+    where I: IntoIterator<Item = Decimal> {
+    let (numer, denom, from_scale) = exact_variance(values, kind)?;
+    // The root at `scale` is the root of the variance at twice that scale.
+    let places = (scale as i64 * 2) - from_scale as i64;
+    let (numer, denom) = if places >= 0 {
+        (upscale(places as u64, numer)?, denom)
+    } else {
+        (numer, upscale(places.unsigned_abs(), denom)?)
+    };
+    to_decimal(rounded_sqrt(numer as u128, denom as u128, mode)? as i128, scale)
+}
+
+/// The square root of `numer / denom`, rounded to an integer according to `mode`.
+fn rounded_sqrt(numer: u128, denom: u128, mode: RoundingMode) -> Result<u128, StatsError> {
+    let root = (numer / denom).isqrt();
+    let exact = root * root * denom == numer;
+    let round_up = match mode {
+        _ if exact => false,
+        RoundingMode::Down | RoundingMode::Floor => false,
+        RoundingMode::Up | RoundingMode::Ceiling => true,
+        RoundingMode::HalfUp | RoundingMode::HalfDown | RoundingMode::HalfEven => {
+            // Compare the value with (root + 1/2)², i.e. 4 × numer with (2 × root + 1)² × denom.
+            let four_numer = numer.checked_mul(4);
+            let midpoint = (2 * root + 1).checked_mul(2 * root + 1).and_then(|m| m.checked_mul(denom));
+            match (four_numer, midpoint) {
+                (Some(a), Some(b)) if a == b => match mode {
+                    RoundingMode::HalfUp => true,
+                    RoundingMode::HalfDown => false,
+                    _ => root % 2 == 1,
+                },
+                (Some(a), Some(b)) => a > b,
+                _ => return Err(StatsError::Overflow),
+            }
+        }
+    };
+    Ok(if round_up { root + 1 } else { root })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StatsError, Variance};
+    use Decimal;
+    use RoundingMode::*;
+
+    fn decimals(s: &str) -> Vec<Decimal> {
+        s.split_whitespace().map(|d| d.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn sums_exactly_at_the_widest_scale() {
+        assert_eq!(Ok(Decimal::new(3006, 3)), super::sum(decimals("1 1.5 0.506")));
+        assert_eq!(Ok(Decimal::new(0, 0)), super::sum(vec![]));
+        assert_eq!(Ok(Decimal::new(1230, 1)), super::sum(decimals("1.2E+2 3.0")));
+        assert_eq!(Err(StatsError::Overflow),
+                   super::sum(vec![Decimal::new(i64::MAX, 0), Decimal::new(1, 0)]));
+        assert_eq!(Ok(Decimal::new(i64::MAX, 0)),
+                   super::sum(vec![Decimal::new(i64::MAX, 0), Decimal::new(1, 0), Decimal::new(-1, 0)]));
+    }
+
+    #[test]
+    fn means_round_once() {
+        assert_eq!(Ok(Decimal::new(33, 1)), super::mean(decimals("1 1 8"), 1, HalfEven));
+        assert_eq!(Ok(Decimal::new(333333, 5)), super::mean(decimals("1 1 8"), 5, Down));
+        assert_eq!(Ok(Decimal::new(-1, 0)), super::mean(decimals("-0.5 -0.6"), 0, Floor));
+        assert_eq!(Ok(Decimal::new(0, 0)), super::mean(decimals("-0.5 -0.6"), 0, Down));
+        assert_eq!(Err(StatsError::Empty), super::mean(vec![], 2, HalfEven));
+    }
+
+    #[test]
+    fn medians() {
+        assert_eq!(Ok(Decimal::new(20, 1)), super::median(decimals("3 2.0 1")));
+        assert_eq!(Ok(Decimal::new(3, 0)), super::median(decimals("4 1 2 8")));
+        assert_eq!(Ok(Decimal::new(-15, 2)), super::median(decimals("-0.1 -0.2")));
+        assert_eq!(Err(StatsError::Empty), super::median(vec![]));
+    }
+
+    #[test]
+    fn percentiles() {
+        let values = decimals("15 20 35 40 50");
+        let at = |p: &str| super::percentile(values.clone(), p.parse().unwrap(), 2, HalfEven);
+        assert_eq!(Ok(Decimal::new(1500, 2)), at("0%"));
+        assert_eq!(Ok(Decimal::new(3500, 2)), at("50%"));
+        assert_eq!(Ok(Decimal::new(5000, 2)), at("100%"));
+        assert_eq!(Ok(Decimal::new(2900, 2)), at("40%"));
+        assert_eq!(Ok(Decimal::new(2075, 2)), at("26.25%"));
+        assert_eq!(Err(StatsError::InvalidPercentile), at("100.1%"));
+        assert_eq!(Err(StatsError::InvalidPercentile), at("-1%"));
+        assert_eq!(Ok(Decimal::new(700, 2)),
+                   super::percentile(decimals("7"), "30%".parse().unwrap(), 2, HalfEven));
+        assert_eq!(Err(StatsError::Empty),
+                   super::percentile(vec![], "30%".parse().unwrap(), 2, HalfEven));
+    }
+
+    #[test]
+    fn weighted_averages() {
+        let pairs = vec![(Decimal::new(2, 0), Decimal::new(1, 0)),
+                         (Decimal::new(5, 0), Decimal::new(2, 0))];
+        assert_eq!(Ok(Decimal::new(4, 0)), super::weighted_average(pairs, 0, HalfEven));
+        let pairs = vec![(Decimal::new(1, 0), Decimal::new(5, 1)),
+                         (Decimal::new(2, 0), Decimal::new(25, 2))];
+        assert_eq!(Ok(Decimal::new(133, 2)), super::weighted_average(pairs, 2, HalfEven));
+        let offsetting = vec![(Decimal::new(1, 0), Decimal::new(1, 0)),
+                              (Decimal::new(2, 0), Decimal::new(-1, 0))];
+        assert_eq!(Err(StatsError::ZeroWeight), super::weighted_average(offsetting, 2, HalfEven));
+        assert_eq!(Err(StatsError::Empty), super::weighted_average(vec![], 2, HalfEven));
+    }
+
+    #[test]
+    fn variances_and_standard_deviations() {
+        let values = decimals("1.0 2.0 3.0 4.0");
+        assert_eq!(Ok(Decimal::new(125, 2)),
+                   super::variance(values.clone(), Variance::Population, 2, HalfEven));
+        assert_eq!(Ok(Decimal::new(1667, 3)),
+                   super::variance(values.clone(), Variance::Sample, 3, HalfEven));
+        assert_eq!(Ok(Decimal::new(1118, 3)),
+                   super::std_dev(values.clone(), Variance::Population, 3, HalfEven));
+        assert_eq!(Ok(Decimal::new(1119, 3)),
+                   super::std_dev(values.clone(), Variance::Population, 3, Up));
+        assert_eq!(Ok(Decimal::new(1, 0)),
+                   super::std_dev(values, Variance::Population, 0, HalfEven));
+        assert_eq!(Ok(Decimal::new(0, 4)),
+                   super::std_dev(decimals("5"), Variance::Population, 4, Up));
+        assert_eq!(Err(StatsError::NotEnoughValues),
+                   super::variance(decimals("5"), Variance::Sample, 2, HalfEven));
+        assert_eq!(Err(StatsError::Empty),
+                   super::std_dev(vec![], Variance::Population, 2, HalfEven));
+    }
+
+    #[test]
+    fn square_roots_round_ties_by_mode() {
+        // The standard deviation of {0, 3} is exactly 1.5.
+        let values = decimals("0 3");
+        assert_eq!(Ok(Decimal::new(2, 0)), super::std_dev(values.clone(), Variance::Population, 0, HalfUp));
+        assert_eq!(Ok(Decimal::new(1, 0)), super::std_dev(values.clone(), Variance::Population, 0, HalfDown));
+        assert_eq!(Ok(Decimal::new(2, 0)), super::std_dev(values, Variance::Population, 0, HalfEven));
+    }
+}
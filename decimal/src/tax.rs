@@ -0,0 +1,385 @@
+//! Itemized sales tax on invoices.
+//!
+//! A `TaxPolicy` lists the taxes a jurisdiction levies, in the order they're applied,
+//! and says how they're computed:
+//!
+//! * `Pricing::Exclusive` treats line amounts as net prices that taxes are added to,
+//!   while `Pricing::Inclusive` treats them as gross prices that already contain the
+//!   taxes, which are then backed out.
+//! * `Rounding::PerLine` rounds each line's taxes, while `Rounding::PerInvoice` rounds
+//!   each tax once on the invoice total and then allocates it across the lines.
+//!
+//! A compound tax is levied on the net amount plus the taxes before it, where a simple
+//! one is levied on the net amount alone.
+//!
+//! However it's computed, a `Breakdown` reconciles exactly: each line's net amount plus
+//! its taxes is its gross amount, and every invoice total is the sum of the lines'.
+//!
+//! # Examples
+//! ```
+//! # use decimal::{Decimal, RoundingMode};
+//! # use decimal::tax::{Rounding, Tax, TaxPolicy};
+//! let mut policy = TaxPolicy::new(vec![Tax::new("GST", "5%".parse().unwrap()),
+//!                                      Tax::compound("PST", "7%".parse().unwrap())],
+//!                                 2, RoundingMode::HalfUp);
+//! let lines = [Decimal::new(250, 2); 3];
+//!
+//! let per_line = policy.compute(&lines).unwrap();
+//! assert_eq!(vec![Decimal::new(13, 2), Decimal::new(18, 2)], per_line.lines[0].taxes);
+//! assert_eq!(vec![Decimal::new(39, 2), Decimal::new(54, 2)], per_line.taxes);
+//!
+//! policy.rounding = Rounding::PerInvoice;
+//! let per_invoice = policy.compute(&lines).unwrap();
+//! assert_eq!(vec![Decimal::new(38, 2), Decimal::new(55, 2)], per_invoice.taxes);
+//! assert_eq!(vec![Decimal::new(12, 2), Decimal::new(18, 2)], per_invoice.lines[2].taxes);
+//! assert_eq!(Decimal::new(843, 2), per_invoice.gross);
+//! ```
+
+use std::cmp::Reverse;
+use std::convert::TryFrom;
+
+use percent::Percent;
+use {checked_upscale_i128, divide_to_scale, scale_diff, ArithmeticError, Decimal, RoundingMode};
+
+/// A named tax at a rate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tax {
+    pub name: String,
+    pub rate: Percent,
+    /// Whether the tax is levied on the taxes before it as well as the net amount.
+    pub compound: bool,
+}
+
+impl Tax {
+    /// A simple tax, levied on the net amount alone.
+    pub fn new(name: &str, rate: Percent) -> Tax {
+        Tax { name: name.to_string(), rate, compound: false }
+    }
+
+    /// A compound tax, levied on the net amount plus the taxes before it.
+    pub fn compound(name: &str, rate: Percent) -> Tax {
+        Tax { name: name.to_string(), rate, compound: true }
+    }
+}
+
+/// Whether line amounts include tax.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pricing {
+    /// Line amounts are net, and taxes are added to them.
+    Exclusive,
+    /// Line amounts are gross, and taxes are backed out of them.
+    Inclusive,
+}
+
+/// Where taxes are rounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Each line's taxes are rounded, and the invoice's are their sums.
+    PerLine,
+    /// Each tax is rounded once on the invoice total, then allocated to the lines in
+    /// proportion to their amounts.
+    PerInvoice,
+}
+
+/// How an invoice is taxed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaxPolicy {
+    /// The taxes levied, in the order they're applied.
+    pub taxes: Vec<Tax>,
+    pub pricing: Pricing,
+    pub rounding: Rounding,
+    /// The scale taxes are rounded to, usually the currency's minor units.
+    pub scale: i32,
+    pub mode: RoundingMode,
+}
+
+/// The taxes on an invoice, line by line and in total.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakdown {
+    pub lines: Vec<LineTaxes>,
+    /// The total of each tax, in the policy's order.
+    pub taxes: Vec<Decimal>,
+    pub net: Decimal,
+    /// The total of all taxes.
+    pub tax: Decimal,
+    pub gross: Decimal,
+}
+
+/// The taxes on one line of an invoice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineTaxes {
+    pub net: Decimal,
+    /// The line's share of each tax, in the policy's order.
+    pub taxes: Vec<Decimal>,
+    pub gross: Decimal,
+}
+
+impl TaxPolicy {
+    /// A policy levying `taxes` on net prices, rounding each line's taxes to `scale`
+    /// according to `mode`.
+    pub fn new(taxes: Vec<Tax>, scale: i32, mode: RoundingMode) -> TaxPolicy {
+        TaxPolicy { taxes, pricing: Pricing::Exclusive, rounding: Rounding::PerLine, scale, mode }
+    }
+
+    /// Computes the taxes on an invoice with the given line amounts, which are net or
+    /// gross according to `pricing`. Fails if an amount overflows, or with
+    /// `DivisionByZero` if inclusive prices can't be split because the rates total -100%.
+    pub fn compute(&self, lines: &[Decimal]) -> Result<Breakdown, ArithmeticError> {
+        let line_taxes = match self.rounding {
+            Rounding::PerLine => lines.iter().map(|&amount| self.taxes_on(amount))
+                .collect::<Result<Vec<_>, _>>()?,
+            Rounding::PerInvoice => {
+                let invoice_taxes = self.taxes_on(sum(lines.iter().cloned(), self.scale)?)?;
+                let shares = invoice_taxes.iter().map(|&tax| allocate(tax, lines))
+                    .collect::<Result<Vec<_>, _>>()?;
+                (0..lines.len()).map(|i| shares.iter().map(|s| s[i]).collect()).collect()
+            }
+        };
+
+        let mut breakdown_lines = Vec::with_capacity(lines.len());
+        for (&amount, taxes) in lines.iter().zip(line_taxes) {
+            let tax = sum(taxes.iter().cloned(), self.scale)?;
+            let (net, gross) = match self.pricing {
+                Pricing::Exclusive => (amount, amount.try_add(tax)?),
+                Pricing::Inclusive => (amount.try_sub(tax)?, amount),
+            };
+            breakdown_lines.push(LineTaxes { net, taxes, gross });
+        }
+        let taxes = (0..self.taxes.len())
+            .map(|i| sum(breakdown_lines.iter().map(|line| line.taxes[i]), self.scale))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Breakdown {
+            net: sum(breakdown_lines.iter().map(|line| line.net), self.scale)?,
+            tax: sum(taxes.iter().cloned(), self.scale)?,
+            gross: sum(breakdown_lines.iter().map(|line| line.gross), self.scale)?,
+            lines: breakdown_lines,
+            taxes,
+        })
+    }
+
+    /// Each tax on `amount`, rounded to `scale`.
+    fn taxes_on(&self, amount: Decimal) -> Result<Vec<Decimal>, ArithmeticError> {
+        match self.pricing {
+            Pricing::Exclusive => {
+                let mut taxes: Vec<Decimal> = Vec::with_capacity(self.taxes.len());
+                for tax in &self.taxes {
+                    let base = if tax.compound {
+                        sum(taxes.iter().cloned(), self.scale)?.try_add(amount)?
+                    } else {
+                        amount
+                    };
+                    taxes.push(self.mul_div(base, tax.rate.as_fraction()?, Decimal::new(1, 0))?);
+                }
+                Ok(taxes)
+            }
+            Pricing::Inclusive => {
+                // Each tax is a fixed multiple of the net amount, and the gross amount is
+                // their sum plus one, so each tax is its share of that.
+                let mut multiples: Vec<Decimal> = Vec::with_capacity(self.taxes.len());
+                for tax in &self.taxes {
+                    let base = if tax.compound {
+                        sum(multiples.iter().cloned(), 0)?.try_add(Decimal::new(1, 0))?
+                    } else {
+                        Decimal::new(1, 0)
+                    };
+                    multiples.push(base.try_mul(tax.rate.as_fraction()?)?);
+                }
+                let gross_multiple = sum(multiples.iter().cloned(), 0)?.try_add(Decimal::new(1, 0))?;
+                multiples.iter()
+                    .map(|&m| self.mul_div(amount, m, gross_multiple))
+                    .collect()
+            }
+        }
+    }
+
+    /// `a * b / d`, computed exactly before rounding to `scale`.
+    fn mul_div(&self, a: Decimal, b: Decimal, d: Decimal) -> Result<Decimal, ArithmeticError> {
+        if d.unscaled == 0 {
+            return Err(ArithmeticError::DivisionByZero);
+        }
+        divide_to_scale(a.unscaled as i128 * b.unscaled as i128, a.scale as i64 + b.scale as i64,
+                        d.unscaled as i128, d.scale as i64, self.scale, self.mode)
+    }
+}
+
+/// The sum of `values`, at no less than `scale`.
+fn sum<I: Iterator<Item = Decimal>>(mut values: I, scale: i32) -> Result<Decimal, ArithmeticError> {
+    values.try_fold(Decimal::new(0, scale), |total, d| total.try_add(d))
+}
+
+/// Splits `total` across lines in proportion to `weights`, so the shares add up to it
+/// exactly. Each line gets its share rounded toward zero (or toward negative infinity,
+/// if its weight's sign is unlike the rest), then the units left over go one each to
+/// the lines that lost the most to rounding, earliest first.
+fn allocate(total: Decimal, weights: &[Decimal]) -> Result<Vec<Decimal>, ArithmeticError> {
+    if total.unscaled < 0 {
+        let shares = allocate(Decimal::new(-total.unscaled, total.scale), weights)?;
+        return Ok(shares.into_iter().map(|s| Decimal::new(-s.unscaled, s.scale)).collect());
+    }
+    let weight_scale = weights.iter().map(|w| w.scale).max().unwrap_or(0);
+    let mut units = weights.iter()
+        .map(|w| checked_upscale_i128(w.unscaled as i128, scale_diff(weight_scale, w.scale)))
+        .collect::<Option<Vec<i128>>>()
+        .ok_or(ArithmeticError::Overflow)?;
+    let mut total_weight = units.iter().try_fold(0i128, |t, &u| t.checked_add(u))
+        .ok_or(ArithmeticError::Overflow)?;
+    if total_weight == 0 {
+        // Every tax on a zero total is zero, so there's nothing to allocate.
+        return Ok(vec![Decimal::new(0, total.scale); weights.len()]);
+    }
+    if total_weight < 0 {
+        total_weight = -total_weight;
+        for u in &mut units {
+            *u = -*u;
+        }
+    }
+
+    let whole = total.unscaled as i128;
+    let mut shares = Vec::with_capacity(units.len());
+    let mut lost = Vec::with_capacity(units.len());
+    for &u in &units {
+        let exact = whole.checked_mul(u).ok_or(ArithmeticError::Overflow)?;
+        shares.push(exact.div_euclid(total_weight));
+        lost.push(exact.rem_euclid(total_weight));
+    }
+    let left_over = whole - shares.iter().sum::<i128>();
+    let mut order: Vec<usize> = (0..shares.len()).collect();
+    order.sort_by_key(|&i| (Reverse(lost[i]), i));
+    for &i in order.iter().take(left_over as usize) {
+        shares[i] += 1;
+    }
+    shares.into_iter()
+        .map(|s| Ok(Decimal::new(i64::try_from(s).map_err(|_| ArithmeticError::Overflow)?,
+                                 total.scale)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{allocate, Breakdown, Pricing, Rounding, Tax, TaxPolicy};
+    use {ArithmeticError, Decimal, RoundingMode};
+
+    fn d(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    fn ds(values: &[&str]) -> Vec<Decimal> {
+        values.iter().map(|s| d(s)).collect()
+    }
+
+    fn policy(pricing: Pricing, rounding: Rounding) -> TaxPolicy {
+        let mut policy = TaxPolicy::new(vec![Tax::new("GST", "5%".parse().unwrap()),
+                                             Tax::compound("QST", "9.975%".parse().unwrap())],
+                                        2, RoundingMode::HalfUp);
+        policy.pricing = pricing;
+        policy.rounding = rounding;
+        policy
+    }
+
+    /// Checks the breakdown adds up, using `Decimal`'s own arithmetic.
+    fn assert_reconciles(b: &Breakdown) {
+        let zero = Decimal::new(0, 0);
+        for line in &b.lines {
+            assert_eq!(line.gross, line.net + line.taxes.iter().fold(zero, |t, &x| t + x));
+        }
+        assert_eq!(b.net, b.lines.iter().fold(zero, |t, l| t + l.net));
+        assert_eq!(b.gross, b.lines.iter().fold(zero, |t, l| t + l.gross));
+        for (i, &total) in b.taxes.iter().enumerate() {
+            assert_eq!(total, b.lines.iter().fold(zero, |t, l| t + l.taxes[i]));
+        }
+        assert_eq!(b.tax, b.taxes.iter().fold(zero, |t, &x| t + x));
+        assert_eq!(b.gross, b.net + b.tax);
+    }
+
+    #[test]
+    fn exclusive_per_line() {
+        let b = policy(Pricing::Exclusive, Rounding::PerLine)
+            .compute(&ds(&["10.99", "0.33", "100"])).unwrap();
+        assert_reconciles(&b);
+        // QST is on 10.99 + 0.55 and on 0.33 + 0.02.
+        assert_eq!(ds(&["0.55", "1.15"]), b.lines[0].taxes);
+        assert_eq!(ds(&["0.02", "0.03"]), b.lines[1].taxes);
+        assert_eq!(ds(&["5.00", "10.47"]), b.lines[2].taxes);
+        assert_eq!(d("100"), b.lines[2].net);
+        assert_eq!(d("115.47"), b.lines[2].gross);
+        assert_eq!(ds(&["5.57", "11.65"]), b.taxes);
+        assert_eq!((d("111.32"), d("17.22"), d("128.54")), (b.net, b.tax, b.gross));
+    }
+
+    #[test]
+    fn exclusive_per_invoice_allocates_rounded_totals() {
+        let b = policy(Pricing::Exclusive, Rounding::PerInvoice)
+            .compute(&ds(&["10.99", "0.33", "100"])).unwrap();
+        assert_reconciles(&b);
+        // GST is 5% of 111.32, and QST 9.975% of 111.32 + 5.57.
+        assert_eq!(ds(&["5.57", "11.66"]), b.taxes);
+        assert_eq!(ds(&["0.55", "1.15"]), b.lines[0].taxes);
+        assert_eq!(ds(&["0.02", "0.04"]), b.lines[1].taxes);
+        assert_eq!(ds(&["5.00", "10.47"]), b.lines[2].taxes);
+    }
+
+    #[test]
+    fn inclusive_backs_taxes_out() {
+        let b = policy(Pricing::Inclusive, Rounding::PerLine).compute(&ds(&["115.47"])).unwrap();
+        assert_reconciles(&b);
+        // 115.47 is 100 plus 5% GST plus 9.975% QST on 105.
+        assert_eq!(ds(&["5.00", "10.47"]), b.taxes);
+        assert_eq!((d("100.00"), d("115.47")), (b.net, b.gross));
+
+        let b = policy(Pricing::Inclusive, Rounding::PerInvoice)
+            .compute(&ds(&["1.15", "1.15", "1.15"])).unwrap();
+        assert_reconciles(&b);
+        // Each line's GST is 0.0500..., but the invoice's is 0.15 to share.
+        assert_eq!(ds(&["0.15", "0.31"]), b.taxes);
+        assert_eq!(ds(&["0.05", "0.11"]), b.lines[0].taxes);
+        assert_eq!(ds(&["0.05", "0.10"]), b.lines[2].taxes);
+        assert_eq!(d("0.99"), b.lines[0].net);
+    }
+
+    #[test]
+    fn refunds_and_empty_invoices() {
+        let b = policy(Pricing::Exclusive, Rounding::PerInvoice)
+            .compute(&ds(&["20.00", "-20.00", "-5.00"])).unwrap();
+        assert_reconciles(&b);
+        assert_eq!(ds(&["-0.25", "-0.52"]), b.taxes);
+        assert_eq!(d("-5.77"), b.gross);
+
+        let b = policy(Pricing::Exclusive, Rounding::PerInvoice).compute(&[]).unwrap();
+        assert_eq!((vec![], ds(&["0.00", "0.00"])), (b.lines, b.taxes));
+        assert_eq!((d("0.00"), d("0.00"), d("0.00")), (b.net, b.tax, b.gross));
+
+        let untaxed = TaxPolicy::new(vec![], 2, RoundingMode::HalfEven).compute(&ds(&["1.5"])).unwrap();
+        assert_eq!((d("1.50"), d("0.00"), d("1.50")), (untaxed.net, untaxed.tax, untaxed.gross));
+    }
+
+    #[test]
+    fn rounding_mode_and_scale_apply_to_taxes() {
+        let mut p = policy(Pricing::Exclusive, Rounding::PerLine);
+        p.mode = RoundingMode::Down;
+        assert_eq!(ds(&["0.54", "1.15"]), p.compute(&ds(&["10.99"])).unwrap().taxes);
+        p.scale = 0;
+        assert_eq!(ds(&["0", "1"]), p.compute(&ds(&["10.99"])).unwrap().taxes);
+    }
+
+    #[test]
+    fn overflow_is_an_error() {
+        let p = policy(Pricing::Exclusive, Rounding::PerLine);
+        assert_eq!(Err(ArithmeticError::Overflow), p.compute(&[Decimal::new(i64::MAX, 2)]));
+
+        let mut p = TaxPolicy::new(vec![Tax::new("T", "10000%".parse().unwrap())], 2,
+                                   RoundingMode::HalfUp);
+        p.rounding = Rounding::PerInvoice;
+        assert_eq!(Err(ArithmeticError::Overflow),
+                   p.compute(&ds(&["1000000000000000", "-999999999999999"])));
+    }
+
+    #[test]
+    fn allocation_shares_left_over_units() {
+        assert_eq!(ds(&["0.34", "0.33", "0.33"]), allocate(d("1.00"), &ds(&["1", "1", "1"])).unwrap());
+        assert_eq!(ds(&["0.33", "0.67"]), allocate(d("1.00"), &ds(&["1", "2.0"])).unwrap());
+        assert_eq!(ds(&["-0.34", "-0.33", "-0.33"]),
+                   allocate(d("-1.00"), &ds(&["1", "1", "1"])).unwrap());
+        assert_eq!(ds(&["2.00", "-1.00"]), allocate(d("1.00"), &ds(&["2", "-1"])).unwrap());
+        assert_eq!(ds(&["0", "0"]), allocate(d("0"), &ds(&["1", "-1"])).unwrap());
+    }
+}
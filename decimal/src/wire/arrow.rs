@@ -0,0 +1,122 @@
+//! Apache Arrow's fixed-width `Decimal128` representation: the unscaled value as a
+//! 16-byte little-endian two's complement integer. Precision and scale live in the
+//! column's type rather than in each value, so both are passed in explicitly.
+//!
+//! # Examples
+//! ```
+//! # use decimal::Decimal;
+//! use decimal::wire::arrow;
+//! let bytes = arrow::encode_decimal128(&Decimal::new(125, 2), 5, 3).unwrap();
+//! assert_eq!(&bytes[..2], &[0xe2, 0x04]); // 1250 at scale 3
+//! assert_eq!(arrow::decode_decimal128(&bytes, 5, 3), Ok(Decimal::new(1250, 3)));
+//! ```
+
+use std::convert::TryFrom;
+
+use Decimal;
+use super::{checked_upscale, exact_downscale, WireError};
+
+/// The largest precision a `Decimal128` column may declare.
+pub const MAX_DECIMAL128_PRECISION: u8 = 38;
+
+/// Encodes `d` for a `Decimal128(precision, scale)` column. Fails if `d` has digits
+/// beyond `scale` or more than `precision` significant digits at that scale.
+pub fn encode_decimal128(d: &Decimal, precision: u8, scale: i8) -> Result<[u8; 16], WireError> {
+    validate_precision(precision)?;
+    let unscaled = d.unscaled as i128;
+    let current_scale = d.scale as i64;
+    let target_scale = scale as i64;
+    let unscaled = if target_scale >= current_scale {
+        checked_upscale(unscaled, (target_scale - current_scale) as u32)
+            .ok_or(WireError::PrecisionExceeded(precision))?
+    } else {
+        let down_by = u32::try_from(current_scale - target_scale).map_err(|_| WireError::Inexact)?;
+        exact_downscale(unscaled, down_by).ok_or(WireError::Inexact)?
+    };
+    validate_digits(unscaled, precision)?;
+    Ok(unscaled.to_le_bytes())
+}
+
+/// Decodes a value from a `Decimal128(precision, scale)` column.
+pub fn decode_decimal128(bytes: &[u8; 16], precision: u8, scale: i8) -> Result<Decimal, WireError> {
+    validate_precision(precision)?;
+    let unscaled = i128::from_le_bytes(*bytes);
+    validate_digits(unscaled, precision)?;
+    let unscaled = i64::try_from(unscaled).map_err(|_| WireError::Overflow)?;
+    Ok(Decimal::new(unscaled, scale as i32))
+}
+
+fn validate_precision(precision: u8) -> Result<(), WireError> {
+    if precision == 0 || precision > MAX_DECIMAL128_PRECISION {
+        Err(WireError::InvalidField("precision"))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_digits(unscaled: i128, precision: u8) -> Result<(), WireError> {
+    if unscaled.unsigned_abs() < 10u128.pow(precision as u32) {
+        Ok(())
+    } else {
+        Err(WireError::PrecisionExceeded(precision))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use Decimal;
+    use wire::WireError;
+    use super::{decode_decimal128, encode_decimal128};
+
+    fn le_bytes(prefix: &[u8], fill: u8) -> [u8; 16] {
+        let mut bytes = [fill; 16];
+        bytes[..prefix.len()].copy_from_slice(prefix);
+        bytes
+    }
+
+    #[test]
+    fn encodes_and_decodes_known_byte_vectors() {
+        let one_twenty_three_forty_five = le_bytes(&[0x39, 0x30], 0);
+        assert_eq!(Ok(one_twenty_three_forty_five),
+                   encode_decimal128(&Decimal::new(12345, 2), 10, 2));
+        assert_eq!(Ok(Decimal::new(12345, 2)),
+                   decode_decimal128(&one_twenty_three_forty_five, 10, 2));
+
+        let minus_one = le_bytes(&[], 0xff);
+        assert_eq!(Ok(minus_one), encode_decimal128(&Decimal::new(-1, 0), 1, 0));
+        assert_eq!(Ok(Decimal::new(-1, 0)), decode_decimal128(&minus_one, 1, 0));
+
+        let i64_min = le_bytes(&[0, 0, 0, 0, 0, 0, 0, 0x80], 0xff);
+        assert_eq!(Ok(i64_min), encode_decimal128(&Decimal::new(i64::MIN, 0), 38, 0));
+        assert_eq!(Ok(Decimal::new(i64::MIN, 0)), decode_decimal128(&i64_min, 38, 0));
+    }
+
+    #[test]
+    fn rescales_to_the_column_scale_when_exact() {
+        assert_eq!(Ok(le_bytes(&[0xe8, 0x03], 0)), encode_decimal128(&Decimal::new(1, 0), 4, 3));
+        assert_eq!(Ok(le_bytes(&[0x0c], 0)), encode_decimal128(&Decimal::new(1200, 2), 3, 0));
+        assert_eq!(Ok(le_bytes(&[0x0c], 0)), encode_decimal128(&Decimal::new(1200, 0), 3, -2));
+        assert_eq!(Ok(Decimal::new(12, -2)), decode_decimal128(&le_bytes(&[0x0c], 0), 3, -2));
+        assert_eq!(Err(WireError::Inexact), encode_decimal128(&Decimal::new(125, 2), 3, 1));
+    }
+
+    #[test]
+    fn validates_precision() {
+        assert_eq!(Err(WireError::PrecisionExceeded(3)),
+                   encode_decimal128(&Decimal::new(1000, 0), 3, 0));
+        assert_eq!(Err(WireError::PrecisionExceeded(4)),
+                   encode_decimal128(&Decimal::new(1, 0), 4, 4));
+        assert_eq!(Err(WireError::PrecisionExceeded(2)),
+                   decode_decimal128(&le_bytes(&[100], 0), 2, 0));
+        assert_eq!(Err(WireError::InvalidField("precision")),
+                   encode_decimal128(&Decimal::new(1, 0), 39, 0));
+        assert_eq!(Err(WireError::InvalidField("precision")),
+                   decode_decimal128(&le_bytes(&[], 0), 0, 0));
+    }
+
+    #[test]
+    fn rejects_values_too_large_for_decimal() {
+        let ten_to_the_twentieth = 100_000_000_000_000_000_000i128.to_le_bytes();
+        assert_eq!(Err(WireError::Overflow), decode_decimal128(&ten_to_the_twentieth, 38, 0));
+    }
+}
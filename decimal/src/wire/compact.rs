@@ -0,0 +1,336 @@
+//! Compact encodings for storing large numbers of `Decimal`s.
+//!
+//! `encode` writes the unscaled value and then the scale, each as a zigzag varint, so
+//! small amounts like `1.50` take three bytes instead of twelve.
+//!
+//! `encode_key` is longer but order-preserving: comparing two keys byte by byte gives
+//! the same answer as `partial_cmp` on the decimals they encode, which makes them
+//! suitable as sorted keys (e.g. in an LSM tree). Values that compare equal but differ
+//! in scale, such as `1.0` and `1.00`, get distinct keys that sort by scale.
+//!
+//! # Examples
+//! ```
+//! # use decimal::Decimal;
+//! let mut buf = Vec::new();
+//! Decimal::new(150, 2).encode(&mut buf);
+//! assert_eq!(buf, vec![0xac, 0x02, 0x04]);
+//! assert_eq!(Decimal::decode(&buf), Ok((Decimal::new(150, 2), 3)));
+//!
+//! let mut small = Vec::new();
+//! let mut large = Vec::new();
+//! Decimal::new(-5, 0).encode_key(&mut small);
+//! Decimal::new(1, 3).encode_key(&mut large);
+//! assert!(small < large);
+//! ```
+
+use std::convert::TryFrom;
+
+use Decimal;
+use super::WireError;
+
+const NEGATIVE_TAG: u8 = 0x01;
+const ZERO_TAG: u8 = 0x02;
+const POSITIVE_TAG: u8 = 0x03;
+const DIGITS_END: u8 = 0x00;
+
+impl Decimal {
+    /// Appends the compact varint encoding of this decimal to `buf`.
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        put_varint(buf, zigzag(self.unscaled));
+        put_varint(buf, zigzag(self.scale as i64));
+    }
+
+    /// Decodes a decimal written by `encode` from the start of `bytes`, returning it
+    /// along with the number of bytes it occupied.
+    pub fn decode(bytes: &[u8]) -> Result<(Decimal, usize), WireError> {
+        let mut pos = 0;
+        let unscaled = unzigzag(get_varint(bytes, &mut pos)?);
+        let scale = i32::try_from(unzigzag(get_varint(bytes, &mut pos)?))
+            .map_err(|_| WireError::Overflow)?;
+        Ok((Decimal::new(unscaled, scale), pos))
+    }
+
+    /// Appends the order-preserving key encoding of this decimal to `buf`.
+    ///
+    /// The key is a sign tag, then for nonzero values the position of the most
+    /// significant digit and the significant digits packed two to a byte (all
+    /// complemented for negative values, so larger magnitudes sort first), and finally
+    /// the scale.
+    pub fn encode_key(&self, buf: &mut Vec<u8>) {
+        if self.unscaled == 0 {
+            buf.push(ZERO_TAG);
+        } else {
+            let mut significand = self.unscaled.unsigned_abs();
+            let mut trailing_zeros = 0i64;
+            while significand.is_multiple_of(10) {
+                significand /= 10;
+                trailing_zeros += 1;
+            }
+            let digits = significand.to_string().into_bytes();
+            let exponent = digits.len() as i64 + trailing_zeros - self.scale as i64;
+
+            let body_start = buf.len() + 1;
+            buf.push(if self.unscaled < 0 { NEGATIVE_TAG } else { POSITIVE_TAG });
+            put_ordered_int(buf, exponent);
+            for pair in digits.chunks(2) {
+                let high = pair[0] - b'0';
+                let low = if pair.len() > 1 { pair[1] - b'0' } else { 0 };
+                buf.push(high * 10 + low + 1);
+            }
+            buf.push(DIGITS_END);
+            if self.unscaled < 0 {
+                for byte in &mut buf[body_start..] {
+                    *byte = !*byte;
+                }
+            }
+        }
+        put_ordered_int(buf, self.scale as i64);
+    }
+
+    /// Decodes a key written by `encode_key`. The key must make up all of `bytes`.
+    pub fn decode_key(bytes: &[u8]) -> Result<Decimal, WireError> {
+        let invalid_length = WireError::InvalidLength(bytes.len());
+        let tag = *bytes.first().ok_or_else(|| invalid_length.clone())?;
+        let mut pos = 1;
+        let (negative, significand, exponent) = match tag {
+            ZERO_TAG => (false, 0, 0),
+            NEGATIVE_TAG | POSITIVE_TAG => {
+                let negative = tag == NEGATIVE_TAG;
+                let body: Vec<u8> = bytes[1..].iter()
+                    .map(|&b| if negative { !b } else { b })
+                    .collect();
+                let mut body_pos = 0;
+                let exponent = get_ordered_int(&body, &mut body_pos)?;
+                let mut significand: u128 = 0;
+                let mut digit_count = 0i64;
+                loop {
+                    let byte = *body.get(body_pos).ok_or_else(|| invalid_length.clone())?;
+                    body_pos += 1;
+                    if byte == DIGITS_END {
+                        break;
+                    }
+                    if byte > 100 {
+                        return Err(WireError::InvalidField("digit pair"));
+                    }
+                    significand = significand.checked_mul(100)
+                        .and_then(|s| s.checked_add((byte - 1) as u128))
+                        .ok_or(WireError::Overflow)?;
+                    digit_count += 2;
+                }
+                if significand == 0 {
+                    return Err(WireError::InvalidField("digit pair"));
+                }
+                while significand.is_multiple_of(10) {
+                    significand /= 10;
+                    digit_count -= 1;
+                }
+                pos += body_pos;
+                let exponent = exponent.checked_sub(digit_count)
+                    .ok_or(WireError::InvalidField("exponent"))?;
+                (negative, significand, exponent)
+            }
+            _ => return Err(WireError::InvalidField("sign tag")),
+        };
+        let scale = get_ordered_int(bytes, &mut pos)?;
+        if pos != bytes.len() {
+            return Err(invalid_length);
+        }
+        let scale = i32::try_from(scale).map_err(|_| WireError::InvalidField("scale"))?;
+        // The value is significand * 10^exponent, so the unscaled value has this many zeros.
+        let zeros = exponent.checked_add(scale as i64)
+            .ok_or(WireError::InvalidField("exponent"))?;
+        let zeros = u32::try_from(zeros).map_err(|_| WireError::InvalidField("scale"))?;
+        let magnitude = 10i128.checked_pow(zeros)
+            .and_then(|factor| (significand as i128).checked_mul(factor))
+            .ok_or(WireError::Overflow)?;
+        let unscaled = i64::try_from(if negative { -magnitude } else { magnitude })
+            .map_err(|_| WireError::Overflow)?;
+        Ok(Decimal::new(unscaled, scale))
+    }
+}
+
+/// Interleaves negative and positive numbers so small magnitudes stay small.
+fn zigzag(n: i64) -> u64 {
+    ((n << 1) ^ (n >> 63)) as u64
+}
+
+fn unzigzag(n: u64) -> i64 {
+    ((n >> 1) as i64) ^ -((n & 1) as i64)
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+fn get_varint(bytes: &[u8], pos: &mut usize) -> Result<u64, WireError> {
+    let mut n: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes.get(*pos).ok_or(WireError::InvalidLength(bytes.len()))?;
+        *pos += 1;
+        if shift == 63 && byte > 1 {
+            return Err(WireError::Overflow);
+        }
+        n |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+        shift += 7;
+    }
+}
+
+/// Writes `n` so that encodings compare bytewise in numeric order: a prefix byte
+/// recording the sign and the count of significant bytes, then those bytes big-endian.
+fn put_ordered_int(buf: &mut Vec<u8>, n: i64) {
+    let bytes = n.to_be_bytes();
+    if n >= 0 {
+        let len = 8 - bytes.iter().take_while(|&&b| b == 0).count();
+        buf.push(0x80 + len as u8);
+        buf.extend_from_slice(&bytes[8 - len..]);
+    } else {
+        let len = 8 - bytes.iter().take_while(|&&b| b == 0xff).count();
+        buf.push(0x7f - len as u8);
+        buf.extend_from_slice(&bytes[8 - len..]);
+    }
+}
+
+fn get_ordered_int(bytes: &[u8], pos: &mut usize) -> Result<i64, WireError> {
+    let prefix = *bytes.get(*pos).ok_or(WireError::InvalidLength(bytes.len()))?;
+    let (len, mut n) = match prefix {
+        0x80..=0x88 => ((prefix - 0x80) as usize, 0i64),
+        0x77..=0x7f => ((0x7f - prefix) as usize, -1i64),
+        _ => return Err(WireError::InvalidField("integer prefix")),
+    };
+    let end = *pos + 1 + len;
+    let body = bytes.get(*pos + 1..end).ok_or(WireError::InvalidLength(bytes.len()))?;
+    for &byte in body {
+        n = n << 8 | byte as i64;
+    }
+    *pos = end;
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use Decimal;
+    use wire::WireError;
+    use super::{get_ordered_int, put_ordered_int};
+
+    fn encoded(d: Decimal) -> Vec<u8> {
+        let mut buf = Vec::new();
+        d.encode(&mut buf);
+        buf
+    }
+
+    fn key(d: Decimal) -> Vec<u8> {
+        let mut buf = Vec::new();
+        d.encode_key(&mut buf);
+        buf
+    }
+
+    fn samples() -> Vec<Decimal> {
+        vec![Decimal::new(i64::MIN, 0), Decimal::new(i64::MIN, 18), Decimal::new(-1000, 0),
+             Decimal::new(-9999, 2), Decimal::new(-1, 0), Decimal::new(-100, 2),
+             Decimal::new(-123, 3), Decimal::new(-12, 2), Decimal::new(-1, 9), Decimal::new(0, 0),
+             Decimal::new(0, 4), Decimal::new(1, 400), Decimal::new(1, 9), Decimal::new(5, 2),
+             Decimal::new(12, 2), Decimal::new(123, 3), Decimal::new(1, 0), Decimal::new(10, 1),
+             Decimal::new(101, 2), Decimal::new(2, 0), Decimal::new(1, -3), Decimal::new(1000, 0),
+             Decimal::new(12, -2), Decimal::new(12, -19), Decimal::new(-3, -5),
+             Decimal::new(i64::MAX, 3), Decimal::new(i64::MAX, 0)]
+    }
+
+    #[test]
+    fn compact_encoding_known_bytes() {
+        assert_eq!(vec![0x00, 0x00], encoded(Decimal::new(0, 0)));
+        assert_eq!(vec![0x01, 0x00], encoded(Decimal::new(-1, 0)));
+        assert_eq!(vec![0x02, 0x02], encoded(Decimal::new(1, 1)));
+        assert_eq!(vec![0xac, 0x02, 0x04], encoded(Decimal::new(150, 2)));
+        assert_eq!(vec![0x18, 0x25], encoded(Decimal::new(12, -19)));
+        assert_eq!(11, encoded(Decimal::new(i64::MIN, 0)).len());
+    }
+
+    #[test]
+    fn compact_encoding_round_trips() {
+        for &d in samples().iter() {
+            let mut buf = encoded(d);
+            let len = buf.len();
+            buf.push(0xff); // decoding stops at the end of the first value
+            assert_eq!(Ok((d, len)), Decimal::decode(&buf));
+        }
+    }
+
+    #[test]
+    fn compact_decoding_rejects_bad_input() {
+        assert_eq!(Err(WireError::InvalidLength(1)), Decimal::decode(&[0xac]));
+        assert_eq!(Err(WireError::InvalidLength(2)), Decimal::decode(&[0xac, 0x02]));
+        assert_eq!(Err(WireError::Overflow), Decimal::decode(&[0x00, 0x80, 0x80, 0x80, 0x80, 0x20]));
+        assert_eq!(Err(WireError::Overflow), Decimal::decode(&[0xff; 11]));
+    }
+
+    #[test]
+    fn keys_round_trip() {
+        for &d in samples().iter() {
+            assert_eq!(Ok(d), Decimal::decode_key(&key(d)), "key for {}", d);
+        }
+    }
+
+    #[test]
+    fn keys_sort_consistently_with_partial_cmp() {
+        let samples = samples();
+        for &a in samples.iter() {
+            for &b in samples.iter() {
+                match a.partial_cmp(&b).unwrap() {
+                    Ordering::Equal => assert_eq!(a.scale.cmp(&b.scale), key(a).cmp(&key(b)),
+                                                  "{} vs {}", a, b),
+                    ordering => assert_eq!(ordering, key(a).cmp(&key(b)), "{} vs {}", a, b),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn key_known_bytes() {
+        assert_eq!(vec![0x02, 0x80], key(Decimal::new(0, 0)));
+        // 1.50: positive, exponent 1, digits "15", scale 2
+        assert_eq!(vec![0x03, 0x81, 0x01, 0x10, 0x00, 0x81, 0x02], key(Decimal::new(150, 2)));
+        assert_eq!(vec![0x01, !0x81, !0x01, !0x10, !0x00, 0x81, 0x02], key(Decimal::new(-150, 2)));
+    }
+
+    #[test]
+    fn key_decoding_rejects_bad_input() {
+        assert_eq!(Err(WireError::InvalidLength(0)), Decimal::decode_key(&[]));
+        assert_eq!(Err(WireError::InvalidField("sign tag")), Decimal::decode_key(&[0x04, 0x80]));
+        assert_eq!(Err(WireError::InvalidLength(3)), Decimal::decode_key(&[0x02, 0x80, 0x00]));
+        assert_eq!(Err(WireError::InvalidLength(4)), Decimal::decode_key(&[0x03, 0x81, 0x01, 0x10]));
+        assert_eq!(Err(WireError::InvalidField("digit pair")),
+                   Decimal::decode_key(&[0x03, 0x81, 0x01, 0x70, 0x00, 0x80]));
+        // Exponents at the ends of the i64 range, where the digit count or scale tips
+        // them over.
+        assert_eq!(Err(WireError::InvalidField("exponent")),
+                   Decimal::decode_key(&[0x03, 0x77, 0x80, 0, 0, 0, 0, 0, 0, 0, 0x02, 0x00, 0x80]));
+        assert_eq!(Err(WireError::InvalidField("exponent")),
+                   Decimal::decode_key(&[0x03, 0x88, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                                         0x02, 0x00, 0x81, 0x05]));
+    }
+
+    #[test]
+    fn ordered_ints_sort_numerically() {
+        let ints = [i64::MIN, -65537, -65536, -257, -256, -255, -2, -1, 0, 1, 255, 256, i64::MAX];
+        let encodings: Vec<Vec<u8>> = ints.iter().map(|&n| {
+            let mut buf = Vec::new();
+            put_ordered_int(&mut buf, n);
+            let mut pos = 0;
+            assert_eq!(Ok(n), get_ordered_int(&buf, &mut pos));
+            assert_eq!(buf.len(), pos);
+            buf
+        }).collect();
+        for pair in encodings.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} < {:?}", pair[0], pair[1]);
+        }
+    }
+}
@@ -0,0 +1,457 @@
+//! IEEE 754-2008 decimal floating point interchange formats: `decimal64` and
+//! `decimal128`, each in both binary integer decimal (BID) and densely packed decimal
+//! (DPD) encodings.
+//!
+//! An IEEE decimal is a sign, an integer coefficient of at most 16 (or 34) digits and
+//! a power-of-ten exponent, so a `Decimal` maps onto one directly with the exponent
+//! being the negated scale. Conversions are exact whenever the value fits the target;
+//! otherwise excess digits are rounded off according to the given `RoundingMode`. A
+//! `Decimal` too large for the target's exponent range becomes infinity or the largest
+//! finite value, whichever `mode` rounds toward.
+//!
+//! # Examples
+//! ```
+//! # use decimal::{Decimal, RoundingMode};
+//! let price = Decimal::new(-750, 2);
+//! assert_eq!(price.to_decimal64_bid(RoundingMode::HalfEven), 0xB1800000000002EE);
+//! assert_eq!(price.to_decimal64_dpd(RoundingMode::HalfEven), 0xA2300000000003D0);
+//! assert_eq!(Decimal::from_decimal64_dpd(0xA2300000000003D0), Ok(price));
+//! ```
+
+use std::convert::TryFrom;
+
+use {downscale_rounded, Decimal, RoundingMode};
+use super::WireError;
+
+/// The parameters distinguishing `decimal64` from `decimal128`.
+struct Format {
+    total_bits: u32,
+    exponent_bits: u32,
+    digits: u32,
+    bias: i64,
+}
+
+const DECIMAL64: Format = Format { total_bits: 64, exponent_bits: 10, digits: 16, bias: 398 };
+const DECIMAL128: Format = Format { total_bits: 128, exponent_bits: 14, digits: 34, bias: 6176 };
+
+impl Format {
+    fn max_coefficient(&self) -> u128 {
+        10u128.pow(self.digits) - 1
+    }
+
+    fn min_exponent(&self) -> i64 {
+        -self.bias
+    }
+
+    fn max_exponent(&self) -> i64 {
+        (3 << (self.exponent_bits - 2)) - 1 - self.bias
+    }
+
+    fn exponent_mask(&self) -> u128 {
+        (1 << self.exponent_bits) - 1
+    }
+
+    /// Bits 5 through 1 below the sign: the combination field.
+    fn combination(&self, bits: u128) -> u128 {
+        (bits >> (self.total_bits - 6)) & 0x1f
+    }
+
+    fn check_special(&self, bits: u128) -> Result<(), WireError> {
+        match self.combination(bits) {
+            0b11110 => Err(WireError::Infinite),
+            0b11111 => Err(WireError::NotANumber),
+            _ => Ok(()),
+        }
+    }
+
+    fn sign_bit(&self, negative: bool) -> u128 {
+        if negative { 1 << (self.total_bits - 1) } else { 0 }
+    }
+
+    fn is_negative(&self, bits: u128) -> bool {
+        bits >> (self.total_bits - 1) == 1
+    }
+
+    fn infinity(&self, negative: bool) -> u128 {
+        self.sign_bit(negative) | 0b11110 << (self.total_bits - 6)
+    }
+
+    /// Splits `d` into coefficient and biased exponent, rounding off digits that don't
+    /// fit. Returns `None` if `d` rounds to infinity.
+    fn pack(&self, d: &Decimal, mode: RoundingMode) -> Option<(u128, u128)> {
+        let mut coefficient = d.unscaled as i128;
+        let mut exponent = -(d.scale as i64);
+        let excess_digits = (digit_count(coefficient.unsigned_abs()) as i64 - self.digits as i64)
+            .max(self.min_exponent() - exponent);
+        if excess_digits > 0 {
+            coefficient = downscale_rounded(coefficient, excess_digits as u32, mode);
+            exponent += excess_digits;
+            if coefficient.unsigned_abs() > self.max_coefficient() {
+                // Rounding carried into a new digit, e.g. 9999...9 became 10000...0.
+                coefficient /= 10;
+                exponent += 1;
+            }
+        }
+        if exponent > self.max_exponent() {
+            // Trade exponent for trailing zeros in the coefficient, if there's room.
+            let room = if coefficient == 0 {
+                exponent
+            } else {
+                self.digits as i64 - digit_count(coefficient.unsigned_abs()) as i64
+            };
+            let needed = exponent - self.max_exponent();
+            if needed <= room {
+                coefficient *= 10i128.pow(needed.min(self.digits as i64) as u32);
+                exponent -= needed;
+            } else {
+                let negative = d.unscaled < 0;
+                let to_infinity = match mode {
+                    RoundingMode::Down => false,
+                    RoundingMode::Floor => negative,
+                    RoundingMode::Ceiling => !negative,
+                    _ => true,
+                };
+                if to_infinity {
+                    return None;
+                }
+                coefficient = self.max_coefficient() as i128;
+                exponent = self.max_exponent();
+            }
+        }
+        let biased = (exponent + self.bias) as u128;
+        Some((coefficient.unsigned_abs(), biased))
+    }
+
+    fn unpack(&self, negative: bool, coefficient: u128, biased: u128, mode: RoundingMode)
+              -> Result<Decimal, WireError> {
+        let coefficient = if coefficient > self.max_coefficient() {
+            0 // non-canonical coefficients are defined to mean zero
+        } else {
+            coefficient as i128
+        };
+        let coefficient = if negative { -coefficient } else { coefficient };
+        let scale = self.bias - biased as i64;
+        let mut dropped = 0;
+        loop {
+            if let Ok(unscaled) = i64::try_from(downscale_rounded(coefficient, dropped, mode)) {
+                return Ok(Decimal::new(unscaled, (scale - dropped as i64) as i32));
+            }
+            dropped += 1;
+        }
+    }
+
+    fn encode_bid(&self, d: &Decimal, mode: RoundingMode) -> u128 {
+        let negative = d.unscaled < 0;
+        let (coefficient, biased) = match self.pack(d, mode) {
+            Some(packed) => packed,
+            None => return self.infinity(negative),
+        };
+        let small_coefficient_bits = self.total_bits - 1 - self.exponent_bits;
+        if coefficient >> small_coefficient_bits == 0 {
+            self.sign_bit(negative) | biased << small_coefficient_bits | coefficient
+        } else {
+            // The coefficient's top bits are an implied 0b100, flagged by 0b11 after the sign.
+            let large_coefficient_bits = small_coefficient_bits - 2;
+            self.sign_bit(negative) | 0b11 << (self.total_bits - 3)
+                | biased << large_coefficient_bits
+                | coefficient & ((1 << large_coefficient_bits) - 1)
+        }
+    }
+
+    fn decode_bid(&self, bits: u128, mode: RoundingMode) -> Result<Decimal, WireError> {
+        self.check_special(bits)?;
+        let small_coefficient_bits = self.total_bits - 1 - self.exponent_bits;
+        let (coefficient, biased) = if (bits >> (self.total_bits - 3)) & 0b11 == 0b11 {
+            let large_coefficient_bits = small_coefficient_bits - 2;
+            (0b100 << large_coefficient_bits | bits & ((1 << large_coefficient_bits) - 1),
+             (bits >> large_coefficient_bits) & self.exponent_mask())
+        } else {
+            (bits & ((1 << small_coefficient_bits) - 1),
+             (bits >> small_coefficient_bits) & self.exponent_mask())
+        };
+        self.unpack(self.is_negative(bits), coefficient, biased, mode)
+    }
+
+    fn encode_dpd(&self, d: &Decimal, mode: RoundingMode) -> u128 {
+        let negative = d.unscaled < 0;
+        let (coefficient, biased) = match self.pack(d, mode) {
+            Some(packed) => packed,
+            None => return self.infinity(negative),
+        };
+        let trailing_digits = self.digits - 1;
+        let trailing_bits = trailing_digits / 3 * 10;
+        let continuation_bits = self.exponent_bits - 2;
+        let leading_digit = coefficient / 10u128.pow(trailing_digits);
+        let mut trailing = coefficient % 10u128.pow(trailing_digits);
+        let exponent_msbs = biased >> continuation_bits;
+        let combination = if leading_digit < 8 {
+            exponent_msbs << 3 | leading_digit
+        } else {
+            0b11000 | exponent_msbs << 1 | (leading_digit & 1)
+        };
+        let mut declets = 0;
+        for i in 0..(trailing_digits / 3) {
+            declets |= (encode_declet((trailing % 1000) as u16) as u128) << (10 * i);
+            trailing /= 1000;
+        }
+        self.sign_bit(negative) | combination << (self.total_bits - 6)
+            | (biased & ((1 << continuation_bits) - 1)) << trailing_bits
+            | declets
+    }
+
+    fn decode_dpd(&self, bits: u128, mode: RoundingMode) -> Result<Decimal, WireError> {
+        self.check_special(bits)?;
+        let trailing_bits = (self.digits - 1) / 3 * 10;
+        let continuation_bits = self.exponent_bits - 2;
+        let combination = self.combination(bits);
+        let (exponent_msbs, leading_digit) = if combination >> 3 == 0b11 {
+            ((combination >> 1) & 0b11, 8 + (combination & 1))
+        } else {
+            (combination >> 3, combination & 0b111)
+        };
+        let biased = exponent_msbs << continuation_bits
+            | (bits >> trailing_bits) & ((1 << continuation_bits) - 1);
+        let mut coefficient = leading_digit;
+        for i in (0..(trailing_bits / 10)).rev() {
+            let declet = ((bits >> (10 * i)) & 0x3ff) as u16;
+            coefficient = coefficient * 1000 + decode_declet(declet) as u128;
+        }
+        self.unpack(self.is_negative(bits), coefficient, biased, mode)
+    }
+}
+
+fn digit_count(mut n: u128) -> u32 {
+    let mut digits = 1;
+    while n >= 10 {
+        n /= 10;
+        digits += 1;
+    }
+    digits
+}
+
+/// Packs three decimal digits (`0..1000`) into ten bits.
+fn encode_declet(n: u16) -> u16 {
+    let (d2, d1, d0) = (n / 100, n / 10 % 10, n % 10);
+    let (b2, b1, b0) = (d2 & 0b111, d1 & 0b111, d0 & 0b111);
+    match (d2 > 7, d1 > 7, d0 > 7) {
+        (false, false, false) => b2 << 7 | b1 << 4 | b0,
+        (false, false, true) => b2 << 7 | b1 << 4 | 0b1000 | b0,
+        (false, true, false) => b2 << 7 | (b0 & 0b110) << 4 | b1 << 4 | 0b1010 | (b0 & 1),
+        (true, false, false) => (b0 & 0b110) << 7 | b2 << 7 | b1 << 4 | 0b1100 | (b0 & 1),
+        (false, true, true) => b2 << 7 | 0b1000000 | b1 << 4 | 0b1110 | b0,
+        (true, false, true) => (b1 & 0b110) << 7 | b2 << 7 | 0b0100000 | (b1 & 1) << 4 | 0b1110 | b0,
+        (true, true, false) => (b0 & 0b110) << 7 | b2 << 7 | b1 << 4 | 0b1110 | (b0 & 1),
+        (true, true, true) => b2 << 7 | 0b1100000 | b1 << 4 | 0b1110 | b0,
+    }
+}
+
+/// Unpacks ten bits into three decimal digits. Every one of the 1024 patterns decodes,
+/// including the non-canonical ones.
+fn decode_declet(declet: u16) -> u16 {
+    let pqr = declet >> 7;
+    let stu = (declet >> 4) & 0b111;
+    let (p_q, s_t, r, u, y) = (pqr >> 1, stu >> 1, pqr & 1, stu & 1, declet & 1);
+    let (d2, d1, d0) = if declet & 0b1000 == 0 {
+        (pqr, stu, declet & 0b111)
+    } else {
+        match (declet >> 1) & 0b11 {
+            0b00 => (pqr, stu, 8 + y),
+            0b01 => (pqr, 8 + u, s_t << 1 | y),
+            0b10 => (8 + r, stu, p_q << 1 | y),
+            _ => match s_t {
+                0b00 => (8 + r, 8 + u, p_q << 1 | y),
+                0b01 => (8 + r, p_q << 1 | u, 8 + y),
+                0b10 => (pqr, 8 + u, 8 + y),
+                _ => (8 + r, 8 + u, 8 + y),
+            },
+        }
+    };
+    d2 * 100 + d1 * 10 + d0
+}
+
+impl Decimal {
+    /// Encodes as an IEEE 754 `decimal64` in binary integer decimal (BID) form, rounding
+    /// off digits beyond the 16 it can hold.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{Decimal, RoundingMode};
+    /// assert_eq!(Decimal::new(1, 0).to_decimal64_bid(RoundingMode::HalfEven),
+    ///            0x31C0000000000001);
+    /// ```
+    pub fn to_decimal64_bid(&self, mode: RoundingMode) -> u64 {
+        DECIMAL64.encode_bid(self, mode) as u64
+    }
+
+    /// Decodes an IEEE 754 `decimal64` in BID form. Every finite `decimal64` fits exactly.
+    pub fn from_decimal64_bid(bits: u64) -> Result<Decimal, WireError> {
+        DECIMAL64.decode_bid(bits as u128, RoundingMode::Down)
+    }
+
+    /// Encodes as an IEEE 754 `decimal64` in densely packed decimal (DPD) form, rounding
+    /// off digits beyond the 16 it can hold.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::{Decimal, RoundingMode};
+    /// assert_eq!(Decimal::new(1, 0).to_decimal64_dpd(RoundingMode::HalfEven),
+    ///            0x2238000000000001);
+    /// ```
+    pub fn to_decimal64_dpd(&self, mode: RoundingMode) -> u64 {
+        DECIMAL64.encode_dpd(self, mode) as u64
+    }
+
+    /// Decodes an IEEE 754 `decimal64` in DPD form. Every finite `decimal64` fits exactly.
+    pub fn from_decimal64_dpd(bits: u64) -> Result<Decimal, WireError> {
+        DECIMAL64.decode_dpd(bits as u128, RoundingMode::Down)
+    }
+
+    /// Encodes as an IEEE 754 `decimal128` in BID form. Every `Decimal` fits exactly unless
+    /// its scale is beyond the format's exponent range.
+    pub fn to_decimal128_bid(&self, mode: RoundingMode) -> u128 {
+        DECIMAL128.encode_bid(self, mode)
+    }
+
+    /// Decodes an IEEE 754 `decimal128` in BID form. Coefficients too large for an `i64`
+    /// lose their least significant digits, rounded according to `mode`.
+    pub fn from_decimal128_bid(bits: u128, mode: RoundingMode) -> Result<Decimal, WireError> {
+        DECIMAL128.decode_bid(bits, mode)
+    }
+
+    /// Encodes as an IEEE 754 `decimal128` in DPD form. Every `Decimal` fits exactly unless
+    /// its scale is beyond the format's exponent range.
+    pub fn to_decimal128_dpd(&self, mode: RoundingMode) -> u128 {
+        DECIMAL128.encode_dpd(self, mode)
+    }
+
+    /// Decodes an IEEE 754 `decimal128` in DPD form. Coefficients too large for an `i64`
+    /// lose their least significant digits, rounded according to `mode`.
+    pub fn from_decimal128_dpd(bits: u128, mode: RoundingMode) -> Result<Decimal, WireError> {
+        DECIMAL128.decode_dpd(bits, mode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Decimal, RoundingMode};
+    use wire::WireError;
+    use super::{decode_declet, encode_declet};
+
+    const EVEN: RoundingMode = RoundingMode::HalfEven;
+
+    #[test]
+    fn declets_round_trip() {
+        for n in 0..1000 {
+            assert_eq!(n, decode_declet(encode_declet(n)), "declet for {}", n);
+        }
+        assert_eq!(0x0ff, encode_declet(999));
+        assert_eq!(0x3d0, encode_declet(750));
+        assert_eq!(999, decode_declet(0x3ff)); // non-canonical
+    }
+
+    #[test]
+    fn decimal64_known_values() {
+        let cases: [(i64, i32, u64, u64); 5] = [
+            (0, 0, 0x31c0000000000000, 0x2238000000000000),
+            (1, 0, 0x31c0000000000001, 0x2238000000000001),
+            (-750, 2, 0xb1800000000002ee, 0xa2300000000003d0),
+            (1234567890123456, 0, 0x31c462d53c8abac0, 0x263934b9c1e28e56),
+            (9999999999999999, 0, 0x6c7386f26fc0ffff, 0x6e38ff3fcff3fcff),
+        ];
+        for &(unscaled, scale, bid, dpd) in cases.iter() {
+            let d = Decimal::new(unscaled, scale);
+            assert_eq!(bid, d.to_decimal64_bid(EVEN), "BID for {}", d);
+            assert_eq!(dpd, d.to_decimal64_dpd(EVEN), "DPD for {}", d);
+            assert_eq!(Ok(d), Decimal::from_decimal64_bid(bid));
+            assert_eq!(Ok(d), Decimal::from_decimal64_dpd(dpd));
+        }
+    }
+
+    #[test]
+    fn decimal128_known_values() {
+        let cases: [(i64, i32, u128, u128); 3] = [
+            (1, 0, 0x30400000000000000000000000000001, 0x22080000000000000000000000000001),
+            (-750, 2, 0xb03c00000000000000000000000002ee, 0xa20780000000000000000000000003d0),
+            (i64::MAX, 0, 0x30400000000000007fffffffffffffff, 0x2208000000000000948df20da5cfd70d),
+        ];
+        for &(unscaled, scale, bid, dpd) in cases.iter() {
+            let d = Decimal::new(unscaled, scale);
+            assert_eq!(bid, d.to_decimal128_bid(EVEN), "BID for {}", d);
+            assert_eq!(dpd, d.to_decimal128_dpd(EVEN), "DPD for {}", d);
+            assert_eq!(Ok(d), Decimal::from_decimal128_bid(bid, EVEN));
+            assert_eq!(Ok(d), Decimal::from_decimal128_dpd(dpd, EVEN));
+        }
+    }
+
+    #[test]
+    fn decimal64_rounds_coefficients_longer_than_sixteen_digits() {
+        let seventeen_digits = Decimal::new(12345678901234565, 0);
+        let even = Decimal::from_decimal64_bid(seventeen_digits.to_decimal64_bid(EVEN));
+        assert_eq!(Ok(Decimal::new(1234567890123456, -1)), even);
+        let up = Decimal::from_decimal64_dpd(seventeen_digits.to_decimal64_dpd(RoundingMode::Up));
+        assert_eq!(Ok(Decimal::new(1234567890123457, -1)), up);
+
+        let carries = Decimal::new(99999999999999999, 1);
+        assert_eq!(Ok(Decimal::new(1000000000000000, -1)),
+                   Decimal::from_decimal64_bid(carries.to_decimal64_bid(EVEN)));
+    }
+
+    #[test]
+    fn rounds_scales_beyond_the_minimum_exponent() {
+        let tiny = Decimal::new(15, 399);
+        assert_eq!(Ok(Decimal::new(2, 398)),
+                   Decimal::from_decimal64_bid(tiny.to_decimal64_bid(EVEN)));
+        assert_eq!(Ok(Decimal::new(1, 398)),
+                   Decimal::from_decimal64_dpd(tiny.to_decimal64_dpd(RoundingMode::Down)));
+    }
+
+    #[test]
+    fn decimal128_rounds_coefficients_too_large_for_i64() {
+        // 1.000000000000000000000000000000001 (33 places)
+        let coefficient = 10u128.pow(33) + 1;
+        let bid = (6176 - 33) << 113 | coefficient;
+        assert_eq!(Ok(Decimal::new(1_000_000_000_000_000_000, 18)),
+                   Decimal::from_decimal128_bid(bid, EVEN));
+        assert_eq!(Ok(Decimal::new(1_000_000_000_000_000_001, 18)),
+                   Decimal::from_decimal128_bid(bid, RoundingMode::Up));
+        // 10^33 with exponent 0 needs a negative scale
+        assert_eq!(Ok(Decimal::new(1_000_000_000_000_000_000, -15)),
+                   Decimal::from_decimal128_bid(6176 << 113 | 10u128.pow(33), EVEN));
+    }
+
+    #[test]
+    fn positive_exponents_become_negative_scales() {
+        let one_e3 = (398 + 3) << 53 | 1;
+        assert_eq!(Ok(Decimal::new(1, -3)), Decimal::from_decimal64_bid(one_e3));
+        assert_eq!(Ok(Decimal::new(9999999999999999, -369)),
+                   Decimal::from_decimal64_dpd(0x77fcff3fcff3fcff));
+        assert_eq!(0x77fcff3fcff3fcff, Decimal::new(9999999999999999, -369).to_decimal64_dpd(EVEN));
+    }
+
+    #[test]
+    fn exponents_beyond_the_maximum_are_clamped_or_overflow() {
+        assert_eq!(Ok(Decimal::new(500_000_000_000, -369)),
+                   Decimal::from_decimal64_bid(Decimal::new(5, -380).to_decimal64_bid(EVEN)));
+        let too_large = Decimal::new(5, -400);
+        assert_eq!(0x7800000000000000, too_large.to_decimal64_bid(EVEN));
+        assert_eq!(0x77fb86f26fc0ffff, too_large.to_decimal64_bid(RoundingMode::Down));
+        assert_eq!(0x77fcff3fcff3fcff, too_large.to_decimal64_dpd(RoundingMode::Floor));
+        assert_eq!(0xf800000000000000, (-1 * too_large).to_decimal64_dpd(RoundingMode::Floor));
+        assert_eq!(0x78000000000000000000000000000000,
+                   Decimal::new(1, -7000).to_decimal128_bid(RoundingMode::Up));
+    }
+
+    #[test]
+    fn special_values_are_errors() {
+        assert_eq!(Err(WireError::Infinite), Decimal::from_decimal64_bid(0x7800000000000000));
+        assert_eq!(Err(WireError::Infinite), Decimal::from_decimal64_dpd(0xf800000000000000));
+        assert_eq!(Err(WireError::NotANumber), Decimal::from_decimal64_bid(0x7c00000000000000));
+        assert_eq!(Err(WireError::NotANumber),
+                   Decimal::from_decimal128_dpd(0x7e000000000000000000000000000000, EVEN));
+    }
+
+    #[test]
+    fn non_canonical_bid_coefficients_mean_zero() {
+        assert_eq!(Ok(Decimal::new(0, 0)), Decimal::from_decimal64_bid(0x6c7386f26fc10000));
+    }
+}
@@ -0,0 +1,61 @@
+//! Binary encodings for exchanging `Decimal`s with other systems.
+//!
+//! Each submodule speaks one format. They share `WireError` for reporting bytes that
+//! can't be decoded and values that can't be encoded exactly.
+
+use std::error;
+use std::fmt;
+
+pub mod arrow;
+pub mod compact;
+pub mod ieee754;
+pub mod postgres;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WireError {
+    /// The input had the wrong number of bytes for its format.
+    InvalidLength(usize),
+    /// A header field or digit held a value the format doesn't allow.
+    InvalidField(&'static str),
+    /// The input encoded NaN, which `Decimal` can't represent.
+    NotANumber,
+    /// The input encoded an infinity, which `Decimal` can't represent.
+    Infinite,
+    /// The value doesn't fit in an `i64` unscaled value (or in the target format).
+    Overflow,
+    /// The value has more significant digits than the declared precision allows.
+    PrecisionExceeded(u8),
+    /// The value can't be represented at the requested scale without losing digits.
+    Inexact,
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WireError::InvalidLength(len) => write!(f, "invalid encoded length {}", len),
+            WireError::InvalidField(field) => write!(f, "invalid {} in encoded decimal", field),
+            WireError::NotANumber => f.write_str("encoded decimal is NaN"),
+            WireError::Infinite => f.write_str("encoded decimal is infinite"),
+            WireError::Overflow => f.write_str("decimal value out of range"),
+            WireError::PrecisionExceeded(p) =>
+                write!(f, "decimal value has more than {} significant digits", p),
+            WireError::Inexact => f.write_str("decimal value not representable at requested scale"),
+        }
+    }
+}
+
+impl error::Error for WireError {}
+
+/// Multiplies by `10^exp`, returning `None` on overflow.
+fn checked_upscale(n: i128, exp: u32) -> Option<i128> {
+    10i128.checked_pow(exp).and_then(|factor| n.checked_mul(factor))
+}
+
+/// Divides by `10^exp`, returning `None` if that would drop nonzero digits.
+fn exact_downscale(n: i128, exp: u32) -> Option<i128> {
+    match 10i128.checked_pow(exp) {
+        Some(factor) if n % factor == 0 => Some(n / factor),
+        Some(_) => None,
+        None => if n == 0 { Some(0) } else { None },
+    }
+}
@@ -0,0 +1,196 @@
+//! PostgreSQL's binary `NUMERIC` format, as used by `COPY ... BINARY` and the
+//! extended query protocol.
+//!
+//! The encoding is a header of four big-endian 16-bit fields (digit count, weight,
+//! sign and display scale) followed by the base-10000 digit groups, most significant
+//! first. The weight is the power of 10000 of the first group.
+//!
+//! # Examples
+//! ```
+//! # use decimal::Decimal;
+//! use decimal::wire::postgres;
+//! let bytes = postgres::encode(&Decimal::new(15, 1)).unwrap();
+//! assert_eq!(bytes, vec![0, 2, 0, 0, 0, 0, 0, 1, 0, 1, 0x13, 0x88]);
+//! assert_eq!(postgres::decode(&bytes), Ok(Decimal::new(15, 1)));
+//! ```
+
+use std::convert::TryFrom;
+
+use Decimal;
+use super::{checked_upscale, exact_downscale, WireError};
+
+const SIGN_POSITIVE: u16 = 0x0000;
+const SIGN_NEGATIVE: u16 = 0x4000;
+const SIGN_NAN: u16 = 0xC000;
+const SIGN_POSITIVE_INFINITY: u16 = 0xD000;
+const SIGN_NEGATIVE_INFINITY: u16 = 0xF000;
+const MAX_DSCALE: u32 = 0x3FFF;
+const HEADER_LEN: usize = 8;
+
+/// Encodes `d` as a binary `NUMERIC`. Fails only if `d`'s scale exceeds the largest
+/// display scale PostgreSQL supports (16383) or its magnitude exceeds the largest
+/// weight. A negative scale is sent as a display scale of zero.
+pub fn encode(d: &Decimal) -> Result<Vec<u8>, WireError> {
+    if d.scale > MAX_DSCALE as i32 {
+        return Err(WireError::Overflow);
+    }
+    // Pad the exponent (the negated scale) out to a whole number of base-10000 groups.
+    let exponent = -(d.scale as i64);
+    let pad = exponent.rem_euclid(4);
+    let mut remaining = d.unscaled.unsigned_abs() as u128 * 10u128.pow(pad as u32);
+    let lowest_weight = (exponent - pad) / 4;
+    let mut groups = Vec::new();
+    while remaining > 0 {
+        groups.push((remaining % 10_000) as u16);
+        remaining /= 10_000;
+    }
+    let weight = lowest_weight + groups.len() as i64 - 1;
+    if weight > i16::MAX as i64 {
+        return Err(WireError::Overflow);
+    }
+    // Trailing zero groups are implied by the weight, so PostgreSQL omits them.
+    let first_nonzero = groups.iter().position(|&g| g != 0).unwrap_or(groups.len());
+    groups.drain(..first_nonzero);
+    groups.reverse();
+
+    let (weight, sign) = if groups.is_empty() {
+        (0, SIGN_POSITIVE)
+    } else if d.unscaled < 0 {
+        (weight, SIGN_NEGATIVE)
+    } else {
+        (weight, SIGN_POSITIVE)
+    };
+    let mut bytes = Vec::with_capacity(HEADER_LEN + 2 * groups.len());
+    bytes.extend_from_slice(&(groups.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(&(weight as i16).to_be_bytes());
+    bytes.extend_from_slice(&sign.to_be_bytes());
+    bytes.extend_from_slice(&(d.scale.max(0) as u16).to_be_bytes());
+    for group in groups {
+        bytes.extend_from_slice(&group.to_be_bytes());
+    }
+    Ok(bytes)
+}
+
+/// Decodes a binary `NUMERIC`, using its display scale as the `Decimal`'s scale unless
+/// the value only fits in an `i64` at a coarser (possibly negative) one.
+pub fn decode(bytes: &[u8]) -> Result<Decimal, WireError> {
+    if bytes.len() < HEADER_LEN {
+        return Err(WireError::InvalidLength(bytes.len()));
+    }
+    let field = |i: usize| u16::from_be_bytes([bytes[2 * i], bytes[2 * i + 1]]);
+    let ndigits = field(0) as i16;
+    let weight = field(1) as i16 as i32;
+    let sign = field(2);
+    let dscale = field(3);
+    if ndigits < 0 {
+        return Err(WireError::InvalidField("digit count"));
+    }
+    if bytes.len() != HEADER_LEN + 2 * ndigits as usize {
+        return Err(WireError::InvalidLength(bytes.len()));
+    }
+    let negative = match sign {
+        SIGN_POSITIVE => false,
+        SIGN_NEGATIVE => true,
+        SIGN_NAN => return Err(WireError::NotANumber),
+        SIGN_POSITIVE_INFINITY | SIGN_NEGATIVE_INFINITY => return Err(WireError::Infinite),
+        _ => return Err(WireError::InvalidField("sign")),
+    };
+    if dscale as u32 > MAX_DSCALE {
+        return Err(WireError::InvalidField("display scale"));
+    }
+
+    let mut magnitude: i128 = 0;
+    for i in 0..ndigits as usize {
+        let group = field(4 + i);
+        if group >= 10_000 {
+            return Err(WireError::InvalidField("digit"));
+        }
+        magnitude = magnitude.checked_mul(10_000)
+            .and_then(|m| m.checked_add(group as i128))
+            .ok_or(WireError::Overflow)?;
+    }
+    let mut unscaled = if negative { -magnitude } else { magnitude };
+    // `unscaled` now has this many places to the right of the decimal point.
+    let mut groups_scale = 4 * (ndigits as i32 - 1 - weight);
+    let dscale = dscale as i32;
+    if groups_scale > dscale {
+        let unscaled = exact_downscale(unscaled, (groups_scale - dscale) as u32)
+            .ok_or(WireError::Inexact)?;
+        return i64::try_from(unscaled)
+            .map(|unscaled| Decimal::new(unscaled, dscale))
+            .map_err(|_| WireError::Overflow);
+    }
+    if let Some(at_dscale) = checked_upscale(unscaled, (dscale - groups_scale) as u32)
+            .and_then(|n| i64::try_from(n).ok()) {
+        return Ok(Decimal::new(at_dscale, dscale));
+    }
+    // Too large at its display scale, so drop trailing zeros for a coarser scale.
+    while unscaled != 0 && unscaled % 10 == 0 {
+        unscaled /= 10;
+        groups_scale -= 1;
+    }
+    i64::try_from(unscaled)
+        .map(|unscaled| Decimal::new(unscaled, groups_scale))
+        .map_err(|_| WireError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use Decimal;
+    use wire::WireError;
+    use super::{decode, encode};
+
+    fn round_trip(unscaled: i64, scale: i32, bytes: &[u8]) {
+        let d = Decimal::new(unscaled, scale);
+        assert_eq!(Ok(bytes.to_vec()), encode(&d), "encoding {}", d);
+        assert_eq!(Ok(d), decode(bytes), "decoding {}", d);
+    }
+
+    #[test]
+    fn encodes_and_decodes_known_byte_vectors() {
+        round_trip(0, 0, &[0, 0, 0, 0, 0, 0, 0, 0]);
+        round_trip(0, 2, &[0, 0, 0, 0, 0, 0, 0, 2]);
+        round_trip(1, 0, &[0, 1, 0, 0, 0, 0, 0, 0, 0, 1]);
+        round_trip(10000, 0, &[0, 1, 0, 1, 0, 0, 0, 0, 0, 1]);
+        round_trip(12345678, 3, &[0, 3, 0, 1, 0, 0, 0, 3, 0, 1, 0x09, 0x29, 0x1a, 0x7c]);
+        round_trip(-125, 2, &[0, 2, 0, 0, 0x40, 0, 0, 2, 0, 1, 0x09, 0xc4]);
+        round_trip(1234, 8, &[0, 1, 0xff, 0xfe, 0, 0, 0, 8, 0x04, 0xd2]);
+        round_trip(12, -19, &[0, 2, 0, 5, 0, 0, 0, 0, 0, 1, 0x07, 0xd0]);
+        round_trip(i64::MIN, 0,
+                   &[0, 5, 0, 4, 0x40, 0, 0, 0,
+                     0x03, 0x9a, 0x0d, 0x2c, 0x01, 0x70, 0x15, 0x65, 0x16, 0xb0]);
+    }
+
+    #[test]
+    fn decodes_values_postgres_sent_with_extra_or_missing_trailing_zeros() {
+        // 1.50 sent with its trailing zero group spelled out
+        assert_eq!(Ok(Decimal::new(150, 2)),
+                   decode(&[0, 2, 0, 0, 0, 0, 0, 2, 0, 1, 0x13, 0x88]));
+        // 20000 with a display scale of 1
+        assert_eq!(Ok(Decimal::new(200000, 1)),
+                   decode(&[0, 1, 0, 1, 0, 0, 0, 1, 0, 2]));
+        // 10^20, too large for an i64 at its display scale of 2
+        assert_eq!(Ok(Decimal::new(1, -20)),
+                   decode(&[0, 1, 0, 5, 0, 0, 0, 2, 0, 1]));
+    }
+
+    #[test]
+    fn rejects_values_decimal_cannot_hold() {
+        assert_eq!(Err(WireError::NotANumber), decode(&[0, 0, 0, 0, 0xc0, 0, 0, 0]));
+        assert_eq!(Err(WireError::Infinite), decode(&[0, 0, 0, 0, 0xd0, 0, 0, 0]));
+        assert_eq!(Err(WireError::Overflow),
+                   decode(&[0, 5, 0, 4, 0, 0, 0, 0, 0x03, 0x9a, 0x0d, 0x2c, 0x01, 0x70,
+                            0x15, 0x65, 0x16, 0xb1]));
+        assert_eq!(Err(WireError::Inexact), decode(&[0, 1, 0xff, 0xff, 0, 0, 0, 2, 0, 1]));
+        assert_eq!(Err(WireError::Overflow), encode(&Decimal::new(1, 16384)));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert_eq!(Err(WireError::InvalidLength(3)), decode(&[0, 0, 0]));
+        assert_eq!(Err(WireError::InvalidLength(8)), decode(&[0, 1, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(Err(WireError::InvalidField("sign")), decode(&[0, 0, 0, 0, 0x12, 0, 0, 0]));
+        assert_eq!(Err(WireError::InvalidField("digit")),
+                   decode(&[0, 1, 0, 0, 0, 0, 0, 0, 0x27, 0x10]));
+    }
+}
@@ -0,0 +1,243 @@
+//! Spelling out `Decimal`s in words, e.g. for printing checks and legal documents.
+//!
+//! English is built in. Other languages can be supported by implementing `Language`
+//! and passing it to `Decimal::to_words_in`.
+//!
+//! # Examples
+//! ```
+//! # use decimal::Decimal;
+//! # use decimal::words::FractionStyle;
+//! let amount = Decimal::new(125050, 2);
+//! assert_eq!("one thousand two hundred fifty and 50/100",
+//!            amount.to_words(FractionStyle::OverDenominator));
+//! assert_eq!("one thousand two hundred fifty point five zero",
+//!            amount.to_words(FractionStyle::Digits));
+//! ```
+
+use Decimal;
+
+/// How the places after the decimal point are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FractionStyle {
+    /// As a fraction over a power of ten, with the numerator padded to the scale, as on
+    /// checks: `1.05` is "one and 05/100".
+    OverDenominator,
+    /// Digit by digit: `1.05` is "one point zero five".
+    Digits,
+}
+
+/// The words a language needs to spell out a `Decimal`.
+pub trait Language {
+    /// Spells out a whole number, e.g. "one thousand two hundred fifty" for 1250.
+    fn whole(&self, n: u128) -> String;
+
+    /// Spells out a single digit (0 through 9), for `FractionStyle::Digits`.
+    fn digit(&self, d: u32) -> &str;
+
+    /// Precedes a negative amount.
+    fn minus(&self) -> &str;
+
+    /// Joins the whole part to the fraction in `FractionStyle::OverDenominator`.
+    fn and(&self) -> &str;
+
+    /// Joins the whole part to the digits in `FractionStyle::Digits`.
+    fn point(&self) -> &str;
+}
+
+/// American English, without "and" inside whole numbers: "one hundred one".
+#[derive(Debug, Clone, Copy, Default)]
+pub struct English;
+
+const ONES: [&str; 20] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    "ten", "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen",
+    "eighteen", "nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+/// Names for successive powers of a thousand, enough for any `u128`.
+const THOUSANDS: [&str; 13] = [
+    "", "thousand", "million", "billion", "trillion", "quadrillion", "quintillion",
+    "sextillion", "septillion", "octillion", "nonillion", "decillion", "undecillion",
+];
+
+impl English {
+    /// Spells out 1 through 999.
+    fn below_thousand(n: u128, words: &mut Vec<String>) {
+        let (hundreds, rest) = (n / 100, (n % 100) as usize);
+        if hundreds > 0 {
+            words.push(format!("{} hundred", ONES[hundreds as usize]));
+        }
+        match rest {
+            0 => {},
+            1..=19 => words.push(ONES[rest].to_string()),
+            _ if rest % 10 == 0 => words.push(TENS[rest / 10].to_string()),
+            _ => words.push(format!("{}-{}", TENS[rest / 10], ONES[rest % 10])),
+        }
+    }
+}
+
+impl Language for English {
+    fn whole(&self, n: u128) -> String {
+        if n == 0 {
+            return ONES[0].to_string();
+        }
+        let mut groups = Vec::new();
+        let mut rest = n;
+        while rest > 0 {
+            groups.push(rest % 1000);
+            rest /= 1000;
+        }
+        let mut words = Vec::new();
+        for (power, &group) in groups.iter().enumerate().rev() {
+            if group > 0 {
+                English::below_thousand(group, &mut words);
+                if power > 0 {
+                    words.push(THOUSANDS[power].to_string());
+                }
+            }
+        }
+        words.join(" ")
+    }
+
+    fn digit(&self, d: u32) -> &str {
+        ONES[d as usize]
+    }
+
+    fn minus(&self) -> &str {
+        "minus"
+    }
+
+    fn and(&self) -> &str {
+        "and"
+    }
+
+    fn point(&self) -> &str {
+        "point"
+    }
+}
+
+impl Decimal {
+    /// Spells out this decimal in English. Places after the decimal point (all of them,
+    /// including trailing zeros) are written according to `style`.
+    ///
+    /// Panics if the whole part is too large for a `u128`, which only happens with a
+    /// scale below -19.
+    pub fn to_words(&self, style: FractionStyle) -> String {
+        self.to_words_in(&English, style)
+    }
+
+    /// Spells out this decimal in the given language.
+    ///
+    /// # Examples
+    /// ```
+    /// # use decimal::Decimal;
+    /// # use decimal::words::{English, FractionStyle};
+    /// assert_eq!("minus forty-two and 007/1000",
+    ///            Decimal::new(-42007, 3).to_words_in(&English, FractionStyle::OverDenominator));
+    /// ```
+    pub fn to_words_in<L: Language + ?Sized>(&self, language: &L, style: FractionStyle) -> String {
+        let magnitude = self.unscaled.unsigned_abs() as u128;
+        let (whole, fraction) = if self.scale > 0 {
+            match 10u128.checked_pow(self.scale as u32) {
+                Some(denominator) => (magnitude / denominator, magnitude % denominator),
+                None => (0, magnitude),
+            }
+        } else {
+            let whole = 10u128.checked_pow(-(self.scale as i64) as u32)
+                .and_then(|factor| magnitude.checked_mul(factor))
+                .expect("decimal too large to spell out");
+            (whole, 0)
+        };
+        let mut words = Vec::new();
+        if self.unscaled < 0 {
+            words.push(language.minus().to_string());
+        }
+        words.push(language.whole(whole));
+        if self.scale > 0 {
+            let places = self.scale as usize;
+            let digits = format!("{:0width$}", fraction, width = places);
+            match style {
+                FractionStyle::OverDenominator => {
+                    words.push(language.and().to_string());
+                    words.push(format!("{}/1{}", digits, "0".repeat(places)));
+                },
+                FractionStyle::Digits => {
+                    words.push(language.point().to_string());
+                    for c in digits.chars() {
+                        words.push(language.digit(c.to_digit(10).unwrap()).to_string());
+                    }
+                },
+            }
+        }
+        words.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{English, FractionStyle, Language};
+    use super::FractionStyle::*;
+    use Decimal;
+
+    #[test]
+    fn spells_whole_numbers() {
+        assert_eq!("zero", English.whole(0));
+        assert_eq!("seven", English.whole(7));
+        assert_eq!("nineteen", English.whole(19));
+        assert_eq!("forty", English.whole(40));
+        assert_eq!("ninety-nine", English.whole(99));
+        assert_eq!("one hundred one", English.whole(101));
+        assert_eq!("one million", English.whole(1_000_000));
+        assert_eq!("two billion three hundred thousand twelve", English.whole(2_000_300_012));
+        assert_eq!("nine quintillion two hundred twenty-three quadrillion three hundred \
+                    seventy-two trillion thirty-six billion eight hundred fifty-four \
+                    million seven hundred seventy-five thousand eight hundred seven",
+                   English.whole(i64::MAX as u128));
+        assert!(English.whole(u128::MAX).starts_with("three hundred forty undecillion"));
+    }
+
+    #[test]
+    fn spells_fractions_in_either_style() {
+        let amount = Decimal::new(100, 2);
+        assert_eq!("one and 00/100", amount.to_words(OverDenominator));
+        assert_eq!("one point zero zero", amount.to_words(Digits));
+        assert_eq!("zero and 05/100", Decimal::new(5, 2).to_words(OverDenominator));
+        assert_eq!("minus zero point five", Decimal::new(-5, 1).to_words(Digits));
+        assert_eq!("twelve", Decimal::new(12, 0).to_words(OverDenominator));
+        assert_eq!("one thousand two hundred", Decimal::new(12, -2).to_words(Digits));
+        assert_eq!("zero and 0000000000000000000000000000000000000001/1\
+                    0000000000000000000000000000000000000000",
+                   Decimal::new(1, 40).to_words(OverDenominator));
+    }
+
+    #[test]
+    #[should_panic(expected = "too large to spell out")]
+    fn refuses_values_past_u128() {
+        Decimal::new(1, -40).to_words(Digits);
+    }
+
+    /// A stand-in for another language, to show the pieces are all pluggable.
+    struct Numerals;
+
+    const NUMERALS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+
+    impl Language for Numerals {
+        fn whole(&self, n: u128) -> String { n.to_string() }
+        fn digit(&self, d: u32) -> &str { NUMERALS[d as usize] }
+        fn minus(&self) -> &str { "-" }
+        fn and(&self) -> &str { "&" }
+        fn point(&self) -> &str { "." }
+    }
+
+    #[test]
+    fn spells_in_other_languages() {
+        let amount = Decimal::new(-125050, 2);
+        assert_eq!("- 1250 & 50/100", amount.to_words_in(&Numerals, OverDenominator));
+        let language: &dyn Language = &Numerals;
+        assert_eq!("- 1250 . 5 0", amount.to_words_in(language, FractionStyle::Digits));
+    }
+}
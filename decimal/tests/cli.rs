@@ -0,0 +1,55 @@
+//! Runs the `decimal` calculator binary.
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn decimal(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_decimal"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn evaluates_arguments_as_one_expression() {
+    let output = decimal(&["3.50", "*", "2", "/", "0.25"], "");
+    assert!(output.status.success());
+    assert_eq!("28\n", stdout(&output));
+}
+
+#[test]
+fn evaluates_stdin_line_by_line_with_scale_and_rounding() {
+    let output = decimal(&["--scale", "2", "--rounding=half-up"], "1 / 8\n\n(1 + 2) * 1.005\n");
+    assert!(output.status.success());
+    assert_eq!("0.13\n3.02\n", stdout(&output));
+}
+
+#[test]
+fn reports_errors_with_their_position_and_keeps_going() {
+    let output = decimal(&[], "1 + 2.3.4\n2 * 2\n1 / 0\n");
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!("4\n", stdout(&output));
+    assert_eq!("1 + 2.3.4\n       ^ invalid character '.' in number at index 7\n\
+                1 / 0\ndecimal division by zero\n",
+               stderr(&output));
+}
+
+#[test]
+fn rejects_bad_options() {
+    let output = decimal(&["--rounding", "sideways", "1"], "");
+    assert_eq!(Some(2), output.status.code());
+    assert!(stderr(&output).starts_with("decimal: unknown rounding mode 'sideways'\n"));
+}
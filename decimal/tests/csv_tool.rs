@@ -0,0 +1,56 @@
+//! Runs the `decimal-csv` tool.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+fn decimal_csv(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_decimal-csv"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn summarizes_a_file_by_group() {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("csv_tool_payments.csv");
+    fs::write(&path, "payee,amount\nacme,0.10\n\"zed\",\"1000.00\"\nacme,0.20\n").unwrap();
+    let output = decimal_csv(&["-c", "amount", "-g", "payee", path.to_str().unwrap()], "");
+    assert!(output.status.success());
+    assert_eq!("payee,column,count,total,min,max\n\
+                acme,amount,2,0.30,0.10,0.20\n\
+                zed,amount,1,1000.00,1000.00,1000.00\n",
+               String::from_utf8(output.stdout).unwrap());
+}
+
+#[test]
+fn reports_bad_cells_from_stdin() {
+    let output = decimal_csv(&["--column", "amount"], "amount\n1.00\nabc\n");
+    assert_eq!(Some(1), output.status.code());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8(output.stderr).unwrap()
+                .starts_with("row 3, column amount: abc\n"));
+}
+
+#[test]
+fn rejects_unknown_columns() {
+    let output = decimal_csv(&["--column", "total"], "amount\n1\n");
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!("no column named 'total'\n", String::from_utf8(output.stderr).unwrap());
+}
+
+#[test]
+fn ranges_values_too_far_apart_in_scale_to_align() {
+    // The total's scale of 1 puts 9E+18 out of an i64's reach, which once made finding
+    // the minimum and maximum panic.
+    let output = decimal_csv(&["-c", "n"], "n\n9E+18\n-9E+18\n0.1\n");
+    assert!(output.status.success());
+    assert_eq!("column,count,total,min,max\nn,3,0.1,-9E+18,9E+18\n",
+               String::from_utf8(output.stdout).unwrap());
+}
@@ -0,0 +1,58 @@
+//! Inputs that crashed (or would have crashed) the fuzz targets in `fuzz/`, kept here so
+//! they stay fixed without needing a nightly toolchain to check. Each is also in the
+//! target's seed corpus.
+
+extern crate decimal;
+
+use decimal::{ArithmeticError, Decimal, DecimalErrorKind};
+
+fn parse_error(s: &str) -> DecimalErrorKind {
+    s.parse::<Decimal>().unwrap_err().kind().clone()
+}
+
+#[test]
+fn digits_past_i64_report_overflow_instead_of_wrapping() {
+    assert_eq!(DecimalErrorKind::Overflow, parse_error("99999999999999999999"));
+    assert_eq!(DecimalErrorKind::Overflow, parse_error("9223372036854775808"));
+    assert_eq!(DecimalErrorKind::Overflow, parse_error("-9223372036854775809"));
+    assert_eq!(Ok(Decimal::new(i64::MIN, 0)), "-9223372036854775808".parse());
+}
+
+#[test]
+fn second_decimal_point_is_rejected() {
+    assert_eq!(DecimalErrorKind::InvalidChar { character: '.', byte_index: 3, char_index: 3 },
+               parse_error("1.2.3"));
+}
+
+#[test]
+fn signs_and_points_without_digits_are_rejected() {
+    assert_eq!(DecimalErrorKind::NoDigits, parse_error("-"));
+    assert_eq!(DecimalErrorKind::NoDigits, parse_error("."));
+    assert_eq!(DecimalErrorKind::NoDigits, parse_error("-.e1"));
+}
+
+#[test]
+fn saturated_exponent_does_not_overflow_scale() {
+    // crash_saturated_exponent in the parse corpus.
+    assert_eq!(DecimalErrorKind::ExponentOutOfRange,
+               parse_error("-1.580e-7777777777777777777777750"));
+    assert_eq!(DecimalErrorKind::ExponentOutOfRange,
+               parse_error("1e7777777777777777777777750"));
+}
+
+#[test]
+fn extreme_values_round_trip_through_display() {
+    for &d in &[Decimal::new(i64::MIN, 0), Decimal::new(i64::MIN, 19), Decimal::new(i64::MAX, -7),
+                Decimal::new(-1, i16::MAX as i32), Decimal::new(i64::MIN, i16::MIN as i32)] {
+        assert_eq!(Ok(d), d.to_string().parse());
+    }
+}
+
+#[test]
+fn remainder_of_min_by_minus_one_is_zero() {
+    // crash_min_rem_minus_one in the ops corpus.
+    assert_eq!(Ok(Decimal::new(0, -1)),
+               Decimal::new(i64::MIN, -1).try_rem(Decimal::new(-1, -1)));
+    assert_eq!(Err(ArithmeticError::Overflow),
+               Decimal::new(i64::MIN, -1).try_div(Decimal::new(-1, -1)));
+}
@@ -18,33 +18,65 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 // THE SOFTWARE.
 
-#![feature(plugin)]
-#![plugin(quickcheck_macros)]
-
 extern crate decimal;
 extern crate quickcheck;
+extern crate quickcheck_macros;
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
 
-use decimal::Decimal;
+use decimal::{Decimal, RoundingMode};
+use decimal::column::DecimalColumn;
+use decimal::percent::Percent;
+use decimal::tax::{Pricing, Rounding, Tax, TaxPolicy};
 use quickcheck::TestResult;
+use quickcheck_macros::quickcheck;
+
+/// `d`'s unscaled value at `scale`, which must be at least `d.scale`, if it fits.
+fn unscaled_at(d: Decimal, scale: i32) -> Option<i64> {
+    10i64.checked_pow((scale - d.scale) as u32).and_then(|factor| d.unscaled.checked_mul(factor))
+}
+
+/// The sum `a + b` should produce, or `None` if computing it would overflow.
+fn checked_sum(a: Decimal, b: Decimal) -> Option<Decimal> {
+    let scale = a.scale.max(b.scale);
+    let sum = unscaled_at(a, scale)?.checked_add(unscaled_at(b, scale)?)?;
+    Some(Decimal::new(sum, scale))
+}
+
+/// The product `a * b` should produce, or `None` if computing it would overflow.
+fn checked_product(a: Decimal, b: Decimal) -> Option<Decimal> {
+    Some(Decimal::new(a.unscaled.checked_mul(b.unscaled)?, a.scale.checked_add(b.scale)?))
+}
 
 #[quickcheck]
-fn displayed_strings_reparse_as_same_value(unscaled: i64, scale: u32) -> bool {
-    let original = Decimal::new(unscaled, scale);
+fn displayed_strings_reparse_as_same_value(original: Decimal) -> bool {
     let reparsed = format!("{}", original).parse::<Decimal>().unwrap();
     original == reparsed
 }
 
+#[quickcheck]
+fn parsed_strings_display_as_same_text(unscaled: u32, scale: u8) -> bool {
+    let digits = unscaled.to_string();
+    let scale = scale as usize % (digits.len() + 1);
+    let text = if scale == 0 {
+        digits
+    } else {
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        format!("{}.{}", if int.is_empty() { "0" } else { int }, frac)
+    };
+    format!("{}", text.parse::<Decimal>().unwrap()) == text
+}
+
 #[quickcheck]
 fn divide_then_multiply_then_add_remainder_restores_original_value(
-    dividend_unscaled: i64, divisor_unscaled: i64, scale: u32) -> TestResult {
-    let dividend = Decimal::new(dividend_unscaled, scale);
-    let divisor = Decimal::new(divisor_unscaled, scale);
-    if divisor_unscaled == 0 {
+    dividend: Decimal, divisor: Decimal) -> TestResult {
+    if divisor.unscaled == 0 || unscaled_at(dividend, dividend.scale.max(divisor.scale)).is_none() {
         TestResult::discard()
     } else {
         let quotient = dividend / divisor;
         let remainder = dividend % divisor;
-        let rebuilt_dividend = ((quotient * divisor) + remainder).adjust_scale(scale);
+        let rebuilt_dividend = ((quotient * divisor) + remainder).adjust_scale(dividend.scale);
         if dividend == rebuilt_dividend {
             TestResult::passed()
         } else {
@@ -54,3 +86,219 @@ fn divide_then_multiply_then_add_remainder_restores_original_value(
         }
     }
 }
+
+/// Checks `quotient * divisor + remainder == dividend` for one division mode, discarding
+/// cases the truncating operators can't handle.
+fn division_identity_holds<F>(dividend: Decimal, divisor: Decimal, divide: F) -> TestResult
+    where F: Fn(Decimal, Decimal) -> (Decimal, Decimal) {
+    if divisor.unscaled == 0 || unscaled_at(dividend, dividend.scale.max(divisor.scale)).is_none() {
+        return TestResult::discard();
+    }
+    let (quotient, remainder) = divide(dividend, divisor);
+    let rebuilt_dividend = ((quotient * divisor) + remainder).adjust_scale(dividend.scale);
+    if dividend == rebuilt_dividend {
+        TestResult::passed()
+    } else {
+        TestResult::error(
+            format!("dividend:{} divisor:{} quotient:{} remainder:{} rebuilt:{}",
+                    dividend, divisor, quotient, remainder, rebuilt_dividend))
+    }
+}
+
+#[quickcheck]
+fn div_rem_restores_original_value(dividend: Decimal, divisor: Decimal) -> TestResult {
+    division_identity_holds(dividend, divisor, |a, b| a.div_rem(b))
+}
+
+#[quickcheck]
+fn euclidean_division_restores_original_value_with_nonnegative_remainder(
+    dividend: Decimal, divisor: Decimal) -> TestResult {
+    division_identity_holds(dividend, divisor, |a, b| {
+        let remainder = a.rem_euclid(b);
+        assert!(remainder.unscaled >= 0, "negative remainder {}", remainder);
+        (a.div_euclid(b), remainder)
+    })
+}
+
+#[quickcheck]
+fn floor_division_restores_original_value_with_remainder_signed_like_divisor(
+    dividend: Decimal, divisor: Decimal) -> TestResult {
+    division_identity_holds(dividend, divisor, |a, b| {
+        let quotient = a.div_floor(b);
+        let remainder = a - quotient * b;
+        assert!(remainder.unscaled == 0 || remainder.unscaled.signum() == b.unscaled.signum(),
+                "remainder {} signed unlike divisor {}", remainder, b);
+        (quotient, remainder)
+    })
+}
+
+#[quickcheck]
+fn addition_is_commutative(a: Decimal, b: Decimal) -> TestResult {
+    match checked_sum(a, b) {
+        Some(sum) => TestResult::from_bool(a + b == sum && b + a == sum),
+        None => TestResult::discard(),
+    }
+}
+
+#[quickcheck]
+fn addition_is_associative(a: Decimal, b: Decimal, c: Decimal) -> TestResult {
+    let left = checked_sum(a, b).and_then(|ab| checked_sum(ab, c));
+    let right = checked_sum(b, c).and_then(|bc| checked_sum(a, bc));
+    match (left, right) {
+        (Some(left), Some(right)) =>
+            TestResult::from_bool(left == right && (a + b) + c == left && a + (b + c) == right),
+        _ => TestResult::discard(),
+    }
+}
+
+#[quickcheck]
+fn subtraction_undoes_addition(a: Decimal, b: Decimal) -> TestResult {
+    match checked_sum(a, b) {
+        Some(sum) => TestResult::from_bool((sum - b).partial_cmp(&a) == Some(Ordering::Equal)),
+        None => TestResult::discard(),
+    }
+}
+
+#[quickcheck]
+fn multiplication_is_commutative(a: Decimal, b: Decimal) -> TestResult {
+    match checked_product(a, b) {
+        Some(product) => TestResult::from_bool(a * b == product && b * a == product),
+        None => TestResult::discard(),
+    }
+}
+
+#[quickcheck]
+fn multiplication_is_associative(a: Decimal, b: Decimal, c: Decimal) -> TestResult {
+    let left = checked_product(a, b).and_then(|ab| checked_product(ab, c));
+    let right = checked_product(b, c).and_then(|bc| checked_product(a, bc));
+    match (left, right) {
+        (Some(left), Some(right)) =>
+            TestResult::from_bool(left == right && (a * b) * c == left && a * (b * c) == right),
+        _ => TestResult::discard(),
+    }
+}
+
+#[quickcheck]
+fn multiplication_distributes_over_addition(a: Decimal, b: Decimal, c: Decimal) -> TestResult {
+    let left = checked_sum(b, c).and_then(|bc| checked_product(a, bc));
+    let right = checked_product(a, b).and_then(|ab| {
+        checked_product(a, c).and_then(|ac| checked_sum(ab, ac))
+    });
+    match (left, right) {
+        (Some(left), Some(right)) => TestResult::from_bool(left == right),
+        _ => TestResult::discard(),
+    }
+}
+
+#[quickcheck]
+fn ordering_is_antisymmetric(a: Decimal, b: Decimal) -> bool {
+    let ab = a.partial_cmp(&b);
+    ab.is_some() && ab.map(Ordering::reverse) == b.partial_cmp(&a)
+}
+
+#[quickcheck]
+fn ordering_is_transitive(a: Decimal, b: Decimal, c: Decimal) -> bool {
+    let mut sorted = [a, b, c];
+    sorted.sort_by(|x, y| x.partial_cmp(y).unwrap());
+    sorted[0] <= sorted[1] && sorted[1] <= sorted[2] && sorted[0] <= sorted[2]
+}
+
+#[quickcheck]
+fn ordering_agrees_with_the_sign_of_the_difference(a: Decimal, b: Decimal) -> TestResult {
+    match checked_sum(a, Decimal::new(-b.unscaled, b.scale)) {
+        Some(difference) =>
+            TestResult::from_bool(a.partial_cmp(&b) == difference.unscaled.partial_cmp(&0)),
+        None => TestResult::discard(),
+    }
+}
+
+#[quickcheck]
+fn ordering_ignores_scale(d: Decimal, extra_places: u8) -> TestResult {
+    let scale = d.scale + (extra_places % 4) as i32;
+    match unscaled_at(d, scale) {
+        Some(_) => TestResult::from_bool(
+            d.partial_cmp(&d.adjust_scale(scale)) == Some(Ordering::Equal)),
+        None => TestResult::discard(),
+    }
+}
+
+#[quickcheck]
+fn column_sum_matches_adding_one_by_one(values: Vec<Decimal>) -> TestResult {
+    let column = match DecimalColumn::try_from(values.clone()) {
+        Ok(column) if !values.is_empty() => column,
+        _ => return TestResult::discard(),
+    };
+    match values[1..].iter().try_fold(values[0], |sum, &d| checked_sum(sum, d)) {
+        Some(sum) => TestResult::from_bool(column.sum() == Ok(sum.adjust_scale(column.scale()))),
+        None => TestResult::discard(),
+    }
+}
+
+#[quickcheck]
+fn column_masks_agree_with_decimal_ordering(values: Vec<Decimal>, pivot: Decimal) -> TestResult {
+    let column = match DecimalColumn::try_from(values) {
+        Ok(column) => column,
+        Err(_) => return TestResult::discard(),
+    };
+    // Decimal's own comparison needs the difference to fit.
+    let negated = Decimal::new(pivot.unscaled.wrapping_neg(), pivot.scale);
+    if pivot.unscaled == i64::MIN || column.iter().any(|d| checked_sum(d, negated).is_none()) {
+        return TestResult::discard();
+    }
+    let (less, equal, greater) = (column.less_than(pivot), column.equal_to(pivot),
+                                  column.greater_than(pivot));
+    TestResult::from_bool(column.iter().enumerate().all(|(i, d)| {
+        let ordering = d.partial_cmp(&pivot).unwrap();
+        (less[i], equal[i], greater[i]) == (ordering == Ordering::Less,
+                                            ordering == Ordering::Equal,
+                                            ordering == Ordering::Greater)
+    }))
+}
+
+#[quickcheck]
+fn rounding_to_significant_digits_rounds_at_the_right_place(d: Decimal, digits: u8, mode: u8)
+                                                           -> TestResult {
+    if d.scale < i32::MIN + 20 {
+        return TestResult::discard();
+    }
+    let digits = digits as u32 % 19 + 1;
+    let modes = [RoundingMode::Down, RoundingMode::Up, RoundingMode::Floor, RoundingMode::Ceiling,
+                 RoundingMode::HalfUp, RoundingMode::HalfDown, RoundingMode::HalfEven];
+    let mode = modes[mode as usize % modes.len()];
+    let rounded = d.round_to_significant(digits, mode);
+    TestResult::from_bool(rounded.precision() <= digits && (
+        d.round(rounded.scale, mode) == rounded
+            // A carry, as from 9.99 to 10.0, drops one more place.
+            || d.round(rounded.scale + 1, mode) == Decimal::new(rounded.unscaled * 10, rounded.scale + 1)))
+}
+
+#[quickcheck]
+fn tax_breakdowns_reconcile(lines: Vec<(i32, u8)>, rates: (u16, u16), inclusive: bool,
+                            per_invoice: bool) -> bool {
+    let lines: Vec<Decimal> = lines.iter().map(|&(u, s)| Decimal::new(u as i64, (s % 4) as i32))
+        .collect();
+    let rate = |r: u16| Percent::new(Decimal::new((r % 3000) as i64, 2));
+    let mut policy = TaxPolicy::new(vec![Tax::new("a", rate(rates.0)),
+                                         Tax::compound("b", rate(rates.1))],
+                                    2, RoundingMode::HalfEven);
+    if inclusive {
+        policy.pricing = Pricing::Inclusive;
+    }
+    if per_invoice {
+        policy.rounding = Rounding::PerInvoice;
+    }
+    let b = policy.compute(&lines).unwrap();
+    let total = |values: &mut dyn Iterator<Item = Decimal>| {
+        values.fold(Decimal::new(0, 0), |t, d| t + d)
+    };
+    let equal = |a: Decimal, b: Decimal| a.partial_cmp(&b) == Some(Ordering::Equal);
+    b.lines.iter().zip(&lines).all(|(line, &amount)| {
+        equal(line.gross, line.net + total(&mut line.taxes.iter().cloned()))
+            && equal(amount, if inclusive { line.gross } else { line.net })
+    })
+        && (0..2).all(|i| equal(b.taxes[i], total(&mut b.lines.iter().map(|l| l.taxes[i]))))
+        && equal(b.net, total(&mut b.lines.iter().map(|l| l.net)))
+        && equal(b.gross, total(&mut b.lines.iter().map(|l| l.gross)))
+        && equal(b.tax, b.taxes[0] + b.taxes[1])
+        && equal(b.gross, b.net + b.tax)
+}